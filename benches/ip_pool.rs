@@ -0,0 +1,80 @@
+// Comportement du pool sur un /16 (65 534 adresses attribuables)
+use std::hint::black_box;
+use std::net::Ipv4Addr;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use dhcp_demo::ip_pool::{AllocationStrategy, IpPool, Subnet};
+
+fn campus_pool() -> IpPool {
+    let subnet: Subnet = "10.0.0.0/16".parse().unwrap();
+    IpPool::new(subnet, &[], &[Ipv4Addr::new(10, 0, 0, 1)]).unwrap()
+}
+
+// Pool dont toutes les adresses sauf la dernière sont attribuées
+fn nearly_full_pool() -> IpPool {
+    let mut pool = campus_pool();
+    while pool.available() > 1 {
+        pool.lease_ip();
+    }
+    pool
+}
+
+fn bench_ip_pool(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ip_pool_65k");
+
+    group.bench_function("new", |b| b.iter(|| black_box(campus_pool())));
+
+    for strategy in [
+        AllocationStrategy::Sequential,
+        AllocationStrategy::Random,
+        AllocationStrategy::LeastRecentlyReleased,
+        AllocationStrategy::HashOfMac,
+    ] {
+        group.bench_function(format!("lease_all_{}", strategy), |b| {
+            b.iter_batched(
+                || campus_pool().with_strategy(strategy),
+                |mut pool| {
+                    let mut n = 0u32;
+                    while let Some(ip) = pool.lease_ip_for(&format!("02000000{:04X}", n)) {
+                        black_box(ip);
+                        n = n.wrapping_add(1);
+                    }
+                    pool
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+
+    // Dernière adresse libre : le pire cas pour la recherche
+    group.bench_function("lease_last_free", |b| {
+        b.iter_batched(
+            nearly_full_pool,
+            |mut pool| black_box(pool.lease_ip()),
+            BatchSize::LargeInput,
+        )
+    });
+
+    let mut pool = nearly_full_pool();
+    let ip = Ipv4Addr::new(10, 0, 128, 1);
+    group.bench_function("release_then_lease", |b| {
+        b.iter(|| {
+            pool.release_ip(black_box(&ip));
+            black_box(pool.lease_ip())
+        })
+    });
+
+    let mut pool = campus_pool();
+    let ip = Ipv4Addr::new(10, 0, 200, 200);
+    group.bench_function("confirm_then_release", |b| {
+        b.iter(|| {
+            black_box(pool.confirm_lease(black_box(ip)));
+            pool.release_ip(&ip);
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_ip_pool);
+criterion_main!(benches);
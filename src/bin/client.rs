@@ -1,7 +1,25 @@
 use std::net::{UdpSocket, SocketAddr};
-use std::process::Command;
-use std::time::Duration;
+use std::process::{self, Command};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::io;
+use std::env;
+use dhcp_demo::message::{parse_mac, DhcpMessage, MessageType, BOOTREPLY, BOOTREQUEST};
+use dhcp_demo::options::{self, DhcpOption};
+
+// Paramètres demandés au serveur (option 55)
+const REQUESTED_PARAMS: [u8; 8] = [
+    options::SUBNET_MASK,
+    options::ROUTER,
+    options::DOMAIN_NAME_SERVER,
+    options::DOMAIN_NAME,
+    options::IP_ADDRESS_LEASE_TIME,
+    options::SERVER_IDENTIFIER,
+    options::RENEWAL_TIME,
+    options::REBINDING_TIME,
+];
+
+// Délai pendant lequel on attend d'autres OFFER après le premier (plusieurs serveurs)
+const OFFER_WINDOW: Duration = Duration::from_millis(1000);
 
 #[cfg(target_os = "windows")]
 fn get_local_mac() -> Option<String> {
@@ -53,6 +71,85 @@ fn get_local_mac() -> Option<String> {
     None
 }
 
+// Identifiant de transaction (xid) : pas de générateur aléatoire dans les dépendances,
+// on combine l'horloge et le PID.
+fn new_xid() -> u32 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    nanos ^ process::id().rotate_left(16)
+}
+
+// Attend une réponse du serveur pour notre transaction, en ignorant les autres paquets
+fn recv_reply(socket: &UdpSocket, xid: u32) -> io::Result<DhcpMessage> {
+    let mut buf = [0u8; 1500];
+    loop {
+        let len = socket.recv(&mut buf)?;
+        match DhcpMessage::parse(&buf[..len]) {
+            Ok(msg) if msg.op == BOOTREPLY && msg.xid == xid => return Ok(msg),
+            Ok(_) => continue,
+            Err(e) => println!("⚠️ Paquet ignoré : {}", e),
+        }
+    }
+}
+
+// Recueille les OFFER reçus pendant OFFER_WINDOW après le premier, un par serveur
+fn collect_offers(socket: &UdpSocket, xid: u32, first: DhcpMessage) -> io::Result<Vec<DhcpMessage>> {
+    let timeout = socket.read_timeout()?;
+    let deadline = Instant::now() + OFFER_WINDOW;
+    let mut offers = vec![first];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        socket.set_read_timeout(Some(remaining))?;
+        match recv_reply(socket, xid) {
+            Ok(msg) if msg.message_type() == Some(MessageType::Offer) => {
+                // Retransmission d'un serveur déjà entendu : on garde sa première offre
+                if offers.iter().all(|offer| offer.server_identifier() != msg.server_identifier()) {
+                    offers.push(msg);
+                }
+            }
+            Ok(_) => continue,
+            Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => break,
+            Err(e) => return Err(e),
+        }
+    }
+    socket.set_read_timeout(timeout)?;
+    Ok(offers)
+}
+
+// Retient l'offre au bail le plus long ; à égalité, la première reçue
+fn choose_offer(offers: Vec<DhcpMessage>) -> DhcpMessage {
+    let mut best: Option<DhcpMessage> = None;
+    for offer in offers {
+        let better = best
+            .as_ref()
+            .is_none_or(|chosen| offer.lease_time().unwrap_or(0) > chosen.lease_time().unwrap_or(0));
+        if better {
+            best = Some(offer);
+        }
+    }
+    best.expect("au moins une offre")
+}
+
+// Affiche la configuration réseau reçue dans l'ACK
+fn print_config(msg: &DhcpMessage) {
+    for option in &msg.options {
+        match option {
+            DhcpOption::SubnetMask(mask) => println!("   Masque : {}", mask),
+            DhcpOption::Router(routers) => println!("   Passerelle : {:?}", routers),
+            DhcpOption::DomainNameServer(dns) => println!("   DNS : {:?}", dns),
+            DhcpOption::DomainName(domain) => println!("   Domaine : {}", domain),
+            DhcpOption::IpAddressLeaseTime(secs) => println!("   Durée du bail : {} s", secs),
+            DhcpOption::ServerIdentifier(ip) => println!("   Serveur : {}", ip),
+            _ => {}
+        }
+    }
+}
+
 fn main() -> io::Result<()> {
     let socket = UdpSocket::bind("0.0.0.0:0")?;
     socket.set_broadcast(true)?; // Activation du broadcast
@@ -62,68 +159,98 @@ fn main() -> io::Result<()> {
         println!("⚠️ Impossible de récupérer l'adresse MAC locale. Envoi sans MAC.");
         "UNKNOWN".to_string()
     });
+    let mac_bytes = parse_mac(&mac_address).unwrap_or([0u8; 6]);
 
-    let server_addr: SocketAddr = "255.255.255.255:67".parse().expect("Adresse broadcast invalide");
+    // Argument optionnel : adresse du serveur (ex: 127.0.0.1:6767), broadcast par défaut
+    let server_addr: SocketAddr = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "255.255.255.255:67".to_string())
+        .parse()
+        .expect("Adresse serveur invalide");
 
     // Étape 1: Envoi du DISCOVER
-    println!("➡️ Envoi DISCOVER avec MAC {}", mac_address);
-    let discover_msg = format!("DISCOVER:{}", mac_address);
-    socket.send_to(discover_msg.as_bytes(), server_addr)?;
+    let xid = new_xid();
+    println!("➡️ Envoi DISCOVER avec MAC {} (xid {:08x})", mac_address, xid);
+    let mut discover = DhcpMessage::new(BOOTREQUEST, MessageType::Discover, xid, mac_bytes);
+    discover.set_option(DhcpOption::ParameterRequestList(REQUESTED_PARAMS.to_vec()));
+    socket.send_to(&discover.to_bytes(), server_addr)?;
 
-    let mut buf = [0u8; 1024];
-    let len = match socket.recv(&mut buf) {
-        Ok(len) => len,
+    let response = match recv_reply(&socket, xid) {
+        Ok(msg) => msg,
         Err(e) => {
             println!("❌ Erreur de réception: {}", e);
             println!("🔄 Nouvelle tentative dans 3 secondes...");
             std::thread::sleep(Duration::from_secs(3));
-            socket.send_to(discover_msg.as_bytes(), server_addr)?;
-            socket.recv(&mut buf)?
+            socket.send_to(&discover.to_bytes(), server_addr)?;
+            recv_reply(&socket, xid)?
         }
     };
-    
-    let response = String::from_utf8_lossy(&buf[..len]).to_string();
-    println!("⬅️ Réception OFFER : {}", response);
-
-    if response.starts_with("OFFER:") {
-        // Extraction de l'IP offerte (format: "OFFER:IP:MAC")
-        let parts: Vec<&str> = response.split(':').collect();
-        if parts.len() < 3 {
-            println!("❌ Format OFFER invalide: {}", response);
-            return Ok(());
-        }
-        let offered_ip = parts[1];
 
-        // Étape 2: Envoi du REQUEST avec IP + MAC
-        println!("➡️ Envoi REQUEST pour l'IP {}", offered_ip);
-        let request_msg = format!("REQUEST:{}:{}", offered_ip, mac_address);
-        socket.send_to(request_msg.as_bytes(), server_addr)?;
+    if response.message_type() != Some(MessageType::Offer) {
+        println!("❌ Réponse inattendue du serveur : {:?}", response.message_type());
+        return Ok(());
+    }
+    let offers = collect_offers(&socket, xid, response)?;
+    for offer in &offers {
+        println!(
+            "⬅️ Réception OFFER : {} (serveur {}, bail {} s)",
+            offer.yiaddr,
+            offer.server_identifier().map_or("?".to_string(), |ip| ip.to_string()),
+            offer.lease_time().map_or("?".to_string(), |secs| secs.to_string())
+        );
+    }
+    let response = choose_offer(offers);
+    let offered_ip = response.yiaddr;
+    if let Some(server_id) = response.server_identifier() {
+        println!("🤝 Offre retenue : {} du serveur {}", offered_ip, server_id);
+    }
 
-        let len = socket.recv(&mut buf)?;
-        let ack_response = String::from_utf8_lossy(&buf[..len]).to_string();
-        println!("⬅️ Réponse du serveur : {}", ack_response);
+    // Étape 2: Envoi du REQUEST pour l'IP offerte
+    println!("➡️ Envoi REQUEST pour l'IP {}", offered_ip);
+    let mut request = DhcpMessage::new(BOOTREQUEST, MessageType::Request, xid, mac_bytes);
+    request.set_option(DhcpOption::RequestedIpAddress(offered_ip));
+    request.set_option(DhcpOption::ParameterRequestList(REQUESTED_PARAMS.to_vec()));
+    // Diffusé avec l'identifiant du serveur retenu : les autres retirent leur offre
+    if let Some(server_id) = response.server_identifier() {
+        request.set_option(DhcpOption::ServerIdentifier(server_id));
+    }
+    socket.send_to(&request.to_bytes(), server_addr)?;
 
-        if ack_response.starts_with("ACK:") {
-            println!("✅ Bail DHCP accepté pour l'IP {}", offered_ip);
+    // Un OFFER tardif d'un autre serveur porte le même xid : on attend l'ACK ou le NAK
+    let ack_response = loop {
+        let msg = recv_reply(&socket, xid)?;
+        if msg.message_type() != Some(MessageType::Offer) {
+            break msg;
+        }
+    };
+    match ack_response.message_type() {
+        Some(MessageType::Ack) => {
+            println!("✅ Bail DHCP accepté pour l'IP {}", ack_response.yiaddr);
+            print_config(&ack_response);
 
             println!("Appuyez sur Entrée pour libérer l'adresse IP...");
             let mut input = String::new();
             io::stdin().read_line(&mut input)?;
 
             println!("➡️ Envoi RELEASE");
-            socket.send_to(b"RELEASE", server_addr)?;
+            let mut release = DhcpMessage::new(BOOTREQUEST, MessageType::Release, new_xid(), mac_bytes);
+            release.ciaddr = ack_response.yiaddr;
+            if let Some(server_id) = ack_response.server_identifier() {
+                release.set_option(DhcpOption::ServerIdentifier(server_id));
+            }
+            socket.send_to(&release.to_bytes(), server_addr)?;
             println!("🔁 Bail DHCP libéré.");
-        } else {
-            println!("❌ Demande rejetée par le serveur : {}", ack_response);
-            if ack_response.contains("NO_AVAILABLE_IP") {
-                println!("💡 Le serveur n'a plus d'IP disponibles");
+        }
+        Some(MessageType::Nak) => {
+            match ack_response.option(options::MESSAGE) {
+                Some(DhcpOption::Message(reason)) => println!("❌ NAK reçu du serveur : {}", reason),
+                _ => println!("❌ NAK reçu du serveur"),
             }
         }
-    } else if response == "NO_AVAILABLE_IP" {
-        println!("❌ Le serveur n'a plus d'adresses IP disponibles");
-    } else {
-        println!("❌ Réponse inattendue du serveur : {}", response);
+        other => {
+            println!("❌ Demande rejetée par le serveur : {:?}", other);
+        }
     }
 
     Ok(())
-}
\ No newline at end of file
+}
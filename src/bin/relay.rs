@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::env;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::process::exit;
+use std::time::{Duration, Instant};
+use dhcp_demo::message::{DhcpMessage, BOOTREPLY, BOOTREQUEST, CLIENT_PORT, SERVER_PORT};
+use dhcp_demo::options::{self, DhcpOption};
+use dhcp_demo::relay_agent::{self, RelayAgentInfo};
+use dhcp_demo::utils::local_ipv4;
+
+// Au-delà, la requête a traversé trop de relais et est abandonnée (RFC 1542, 4.1.1)
+const MAX_HOPS: u8 = 16;
+// Durée pendant laquelle on retient l'adresse source d'un client pour lui renvoyer la réponse
+const CLIENT_MEMORY: Duration = Duration::from_secs(60);
+
+/// Agent relais DHCP (RFC 1542) : retransmet les requêtes des clients du lien
+/// local aux serveurs en amont, avec `giaddr` renseigné, et leur renvoie les réponses.
+struct Relay {
+    socket: UdpSocket,
+    // Adresse du relais sur le lien des clients, placée dans giaddr
+    giaddr: Ipv4Addr,
+    servers: Vec<SocketAddr>,
+    // Option 82 ajoutée aux requêtes (RFC 3046), si un circuit ou un remote-id est configuré
+    agent_info: Option<RelayAgentInfo>,
+    // Source des requêtes en cours, par (xid, MAC), pour répondre aux clients hors port 68
+    clients: HashMap<(u32, String), (SocketAddr, Instant)>,
+}
+
+impl Relay {
+    fn handle_packet(&mut self, buf: &[u8], src: SocketAddr) {
+        let msg = match DhcpMessage::parse(buf) {
+            Ok(msg) => msg,
+            Err(e) => {
+                println!("❌ Message DHCP invalide de {} : {}", src, e);
+                return;
+            }
+        };
+        match msg.op {
+            BOOTREQUEST => self.forward_request(msg, src),
+            BOOTREPLY => self.forward_reply(msg, src),
+            op => println!("⚠️ Opcode {} inconnu, message de {} ignoré", op, src),
+        }
+    }
+
+    fn forward_request(&mut self, mut msg: DhcpMessage, src: SocketAddr) {
+        if msg.hops >= MAX_HOPS {
+            println!("⛔ Requête de {} ignorée : {} sauts", msg.mac_string(), msg.hops);
+            return;
+        }
+        msg.hops += 1;
+        // Déjà relayée par un autre agent : giaddr désigne toujours le lien d'origine
+        if msg.giaddr.is_unspecified() {
+            msg.giaddr = self.giaddr;
+            if let Some(info) = &self.agent_info {
+                if msg.option(options::RELAY_AGENT_INFORMATION).is_none() {
+                    msg.set_option(DhcpOption::RelayAgentInformation(info.clone()));
+                }
+            }
+        }
+
+        let now = Instant::now();
+        self.clients.retain(|_, (_, seen)| now.duration_since(*seen) < CLIENT_MEMORY);
+        self.clients.insert((msg.xid, msg.mac_string()), (src, now));
+
+        let kind = msg.message_type().map_or("BOOTP".to_string(), |t| t.to_string());
+        let data = msg.to_bytes();
+        for server in &self.servers {
+            println!("➡️ {} de {} (xid {:08x}, saut {}) relayé vers {}", kind, msg.mac_string(), msg.xid, msg.hops, server);
+            if let Err(e) = self.socket.send_to(&data, server) {
+                eprintln!("Erreur envoi vers {}: {}", server, e);
+            }
+        }
+    }
+
+    fn forward_reply(&mut self, mut msg: DhcpMessage, src: SocketAddr) {
+        if !self.servers.iter().any(|server| server.ip() == src.ip()) {
+            println!("⚠️ Réponse d'un serveur inconnu {} ignorée", src);
+            return;
+        }
+        if msg.giaddr != self.giaddr {
+            println!("⚠️ Réponse pour le relais {} ignorée", msg.giaddr);
+            return;
+        }
+        // L'option 82 ne concerne que le relais : elle est retirée avant remise au client (RFC 3046, 2.2)
+        if self.agent_info.is_some() {
+            msg.options.retain(|option| option.code() != options::RELAY_AGENT_INFORMATION);
+        }
+
+        let dest = self.client_addr(&msg);
+        let kind = msg.message_type().map_or("BOOTP".to_string(), |t| t.to_string());
+        println!("⬅️ {} {} de {} remis à {}", kind, msg.yiaddr, src, dest);
+        if let Err(e) = self.socket.send_to(&msg.to_bytes(), dest) {
+            eprintln!("Erreur envoi vers {}: {}", dest, e);
+        }
+    }
+
+    // Destinataire de la réponse : la source de la requête si elle est joignable,
+    // sinon ciaddr, sinon diffusion sur le lien (RFC 2131, 4.1)
+    fn client_addr(&self, msg: &DhcpMessage) -> SocketAddr {
+        let known = self
+            .clients
+            .get(&(msg.xid, msg.mac_string()))
+            .map(|(addr, _)| *addr)
+            .filter(|addr| !addr.ip().is_unspecified());
+        match known {
+            Some(addr) => addr,
+            None if !msg.ciaddr.is_unspecified() => SocketAddr::V4(SocketAddrV4::new(msg.ciaddr, CLIENT_PORT)),
+            None => SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::BROADCAST, CLIENT_PORT)),
+        }
+    }
+}
+
+// Serveur amont au format IP[:PORT], port 67 par défaut
+fn parse_server(arg: &str) -> Option<SocketAddr> {
+    arg.parse()
+        .ok()
+        .or_else(|| Some(SocketAddr::V4(SocketAddrV4::new(arg.parse().ok()?, SERVER_PORT))))
+}
+
+// Option 82 configurée par DHCP_RELAY_CIRCUIT_ID et DHCP_RELAY_REMOTE_ID
+fn agent_info_from_env() -> Option<RelayAgentInfo> {
+    let suboptions: Vec<(u8, Vec<u8>)> = [
+        (relay_agent::CIRCUIT_ID, "DHCP_RELAY_CIRCUIT_ID"),
+        (relay_agent::REMOTE_ID, "DHCP_RELAY_REMOTE_ID"),
+    ]
+    .into_iter()
+    .filter_map(|(code, name)| Some((code, env::var(name).ok().filter(|v| !v.is_empty())?.into_bytes())))
+    .collect();
+    (!suboptions.is_empty()).then(|| RelayAgentInfo::new(suboptions))
+}
+
+fn main() {
+    // relay <IP:PORT d'écoute> <serveur> [serveur…]
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.len() < 2 {
+        eprintln!("Usage : relay <IP:PORT d'écoute> <serveur IP[:PORT]> [serveur…]");
+        exit(1);
+    }
+    let bind_addr = &args[0];
+    let servers: Vec<SocketAddr> = args[1..]
+        .iter()
+        .map(|arg| {
+            parse_server(arg).unwrap_or_else(|| {
+                eprintln!("Adresse serveur invalide : {}", arg);
+                exit(1);
+            })
+        })
+        .collect();
+
+    let socket = UdpSocket::bind(bind_addr).unwrap_or_else(|e| {
+        eprintln!("Erreur de liaison sur {} : {}", bind_addr, e);
+        exit(1);
+    });
+    socket.set_broadcast(true).unwrap();
+
+    // Adresse du relais sur le lien : DHCP_RELAY_GIADDR, l'IP de liaison, ou celle de l'interface
+    // si on écoute sur 0.0.0.0 (nécessaire pour recevoir les diffusions des clients)
+    let giaddr = env::var("DHCP_RELAY_GIADDR")
+        .ok()
+        .and_then(|ip| ip.trim().parse().ok())
+        .or_else(|| match socket.local_addr() {
+            Ok(SocketAddr::V4(addr)) if !addr.ip().is_unspecified() => Some(*addr.ip()),
+            _ => local_ipv4(),
+        })
+        .unwrap_or_else(|| {
+            eprintln!("Impossible de déterminer l'adresse du relais (DHCP_RELAY_GIADDR)");
+            exit(1);
+        });
+
+    println!("📡 Relais DHCP sur {} (giaddr {})", bind_addr, giaddr);
+    for server in &servers {
+        println!("   ↪️ Serveur amont {}", server);
+    }
+    let agent_info = agent_info_from_env();
+    if let Some(info) = &agent_info {
+        println!("   Option 82 : {}", info);
+    }
+
+    let mut relay = Relay { socket, giaddr, servers, agent_info, clients: HashMap::new() };
+    let mut buf = [0; 1500];
+    loop {
+        match relay.socket.recv_from(&mut buf) {
+            Ok((len, src)) => relay.handle_packet(&buf[..len], src),
+            Err(e) => {
+                eprintln!("Erreur recv_from: {}", e);
+                exit(1);
+            }
+        }
+    }
+}
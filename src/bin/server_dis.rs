@@ -1,154 +1,23 @@
-use std::collections::HashMap;
-use std::net::{SocketAddr, UdpSocket};
+use std::net::{Ipv4Addr, UdpSocket};
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::{Duration, SystemTime};
+use std::time::Duration;
 use std::io::{self, Write};
 use std::env;
 use std::process::exit;
-
-// Table OUI simplifiée : OUI (uppercase, sans séparateurs) -> marque
-fn lookup_oui(mac: &str) -> &'static str {
-    let oui_map = [
-        ("3C5A37", "Apple"),
-        ("FCFBFB", "Samsung"),
-        ("A4C138", "Dell"),
-        ("00163E", "Cisco"),
-        ("001A2B", "Hewlett-Packard"),
-        ("F4F5E8", "Sony"),
-        ("F0DE61", "Microsoft"),
-        ("3C5AB4", "Apple"),
-        ("B827EB", "Raspberry Pi Foundation"),
-    ];
-
-    let mac = mac.to_uppercase().replace(":", "").replace("-", "");
-    if mac.len() < 6 {
-        return "Unknown";
-    }
-    let prefix = &mac[0..6];
-    for (oui, vendor) in oui_map.iter() {
-        if *oui == prefix {
-            return vendor;
-        }
-    }
-    "Unknown"
-}
-
-pub struct DHCPState {
-    pub leases: HashMap<SocketAddr, (String, String)>, // IP + MAC
-    pub history: Vec<(SocketAddr, String, String)>,    // addr, IP, MAC
-    pub available_ips: Vec<String>,
-    pub clients_status: HashMap<SocketAddr, bool>,
-    pub socket: UdpSocket,
-}
-
-impl DHCPState {
-    pub fn new(socket: UdpSocket, ip_pool: Vec<String>) -> Self {
-        DHCPState {
-            leases: HashMap::new(),
-            history: Vec::new(),
-            available_ips: ip_pool,
-            clients_status: HashMap::new(),
-            socket,
-        }
-    }
-
-    pub fn handle_message(&mut self, msg: &str, src: SocketAddr) {
-        let now = SystemTime::now();
-        if msg.starts_with("DISCOVER:") {
-            let mac = msg.trim_start_matches("DISCOVER:").trim();
-            println!("\n[{:?}] ******** DORA ********", now);
-            println!("⬅️ DISCOVER reçu de {} avec MAC {}", src, mac);
-            if let Some(ip) = self.available_ips.pop() {
-                let vendor = lookup_oui(mac);
-                println!("➡️ Envoi OFFER {} à {} (Marque: {})", ip, src, vendor);
-                self.leases.insert(src, (ip.clone(), mac.to_string()));
-                self.clients_status.insert(src, true);
-                self.history.push((src, ip.clone(), mac.to_string()));
-                let offer = format!("OFFER:{}:{}", ip, mac);
-                let _ = self.socket.send_to(offer.as_bytes(), src);
-            } else {
-                println!("⚠️ Pas d'IP disponible pour {}", src);
-                let _ = self.socket.send_to(b"NO_AVAILABLE_IP", src);
-            }
-        } else if msg.starts_with("REQUEST:") {
-            let rest = msg.trim_start_matches("REQUEST:").trim();
-            let parts: Vec<&str> = rest.split(':').collect();
-            if parts.len() < 2 {
-                println!("❌ Format REQUEST invalide de {}", src);
-                return;
-            }
-            let requested_ip = parts[0];
-            let mac = parts[1];
-            println!("[{:?}] ⬅️ REQUEST {} reçu de {} avec MAC {}", now, requested_ip, src, mac);
-
-            if self.leases.values().any(|(ip, _)| ip == requested_ip)
-                && self.leases.get(&src) != Some(&(requested_ip.to_string(), mac.to_string()))
-            {
-                println!("❌ IP {} déjà utilisée, envoi DECLINE à {}", requested_ip, src);
-                let _ = self.socket.send_to(b"DECLINE:IP_IN_USE", src);
-            } else {
-                let vendor = lookup_oui(mac);
-                println!("➡️ Envoi ACK {} à {} (Marque: {})", requested_ip, src, vendor);
-                self.leases.insert(src, (requested_ip.to_string(), mac.to_string()));
-                self.clients_status.insert(src, true);
-                self.history.push((src, requested_ip.to_string(), mac.to_string()));
-                let ack = format!("ACK:{}:{}", requested_ip, mac);
-                let _ = self.socket.send_to(ack.as_bytes(), src);
-            }
-        } else if msg.starts_with("RELEASE") {
-            println!("\n[{:?}] ⬅️ RELEASE reçu de {}", now, src);
-            if let Some((ip, mac)) = self.leases.remove(&src) {
-                self.available_ips.push(ip.clone());
-                self.clients_status.remove(&src);
-                println!("🔁 IP {} libérée par {} (MAC {})", ip, src, mac);
-            } else {
-                println!("⚠️ Aucune IP à libérer pour {}", src);
-            }
-        }
-    }
-
-    pub fn afficher_clients(&self) {
-        println!("📋 Clients connectés :");
-        for (addr, (ip, mac)) in &self.leases {
-            let statut = if self.clients_status.get(addr).copied().unwrap_or(false) {
-                "[connecté]"
-            } else {
-                "[déconnecté]"
-            };
-            let vendor = lookup_oui(mac);
-            println!("🔹 {} => {} {} (MAC: {}, Marque: {})", addr, ip, statut, mac, vendor);
-        }
-    }
-
-    pub fn afficher_historique(&self) {
-        println!("📜 Historique des baux :");
-        for (addr, ip, mac) in &self.history {
-            let vendor = lookup_oui(mac);
-            println!("📍 {} => {} (MAC: {}, Marque: {})", addr, ip, mac, vendor);
-        }
-    }
-
-    pub fn supprimer_client(&mut self, client_input: &str) {
-        let maybe_addr: Option<SocketAddr> = client_input.parse().ok();
-
-        match maybe_addr {
-            Some(addr) => {
-                if let Some((ip, mac)) = self.leases.remove(&addr) {
-                    self.available_ips.push(ip.clone());
-                    self.clients_status.remove(&addr);
-                    self.history.push((addr, ip.clone(), mac.clone()));
-
-                    let msg = format!("RELEASED_BY_ADMIN:{}", ip);
-                    let _ = self.socket.send_to(msg.as_bytes(), addr);
-
-                    println!("✅ Client {} supprimé. IP {} libérée.", addr, ip);
-                } else {
-                    println!("⚠️ Aucun client trouvé à cette adresse.");
-                }
-            }
-            None => println!("❌ Format d’adresse invalide."),
-        }
+use dhcp_demo::config::ServerConfig;
+use dhcp_demo::server::{spawn_prober, DhcpServer};
+use dhcp_demo::store::HistoryStore;
+use dhcp_demo::utils::{local_ipv4, lookup_oui};
+
+fn afficher_historique(history: &HistoryStore) {
+    println!("📜 Historique des baux :");
+    for lease in &history.leases {
+        let vendor = lookup_oui(&lease.mac);
+        println!(
+            "📍 {} => {} [{}] (MAC: {}, Marque: {})",
+            lease.addr, lease.ip, lease.state, lease.mac, vendor
+        );
     }
 }
 
@@ -162,38 +31,41 @@ fn main() {
         exit(1);
     });
     socket.set_nonblocking(true).unwrap();
+    socket.set_broadcast(true).unwrap(); // Réponses diffusées aux clients sans adresse
+
+    // Identifiant serveur : l'IP de liaison, ou celle de l'interface si on écoute sur 0.0.0.0
+    let server_ip = bind_addr
+        .split(':')
+        .next()
+        .and_then(|ip| ip.parse::<Ipv4Addr>().ok())
+        .filter(|ip| !ip.is_unspecified())
+        .or_else(local_ipv4)
+        .unwrap_or(Ipv4Addr::new(192, 168, 1, 1));
+    let config = ServerConfig::from_env(server_ip).unwrap_or_else(|e| {
+        eprintln!("Configuration invalide : {}", e);
+        exit(1);
+    });
+    let ip_pool = config.ip_pools().unwrap_or_else(|e| {
+        eprintln!("Configuration du pool d'adresses invalide : {}", e);
+        exit(1);
+    });
+    for pool in ip_pool.pools() {
+        println!("📦 Pool {} : {} adresses disponibles (allocation {})", pool.subnet(), pool.available(), pool.strategy());
+    }
 
-    // Pool IP flexible, par exemple 192.168.1.100 - 192.168.1.199
-    let ip_pool = (100..200)
-        .map(|i| {
-            // On remplace le dernier octet par i, en prenant le préfixe IP de bind_addr
-            // Pour simplicité, on extrait le préfixe IP avant le dernier point :
-            let ip_prefix = bind_addr.split(':').next().unwrap_or("192.168.1.0");
-            let mut parts = ip_prefix.split('.').collect::<Vec<_>>();
-            if parts.len() == 4 {
-                let new_val = i.to_string();
-                parts[3] = &new_val;
-                parts.join(".")
-            } else {
-                format!("192.168.1.{}", i)
-            }
-        })
-        .collect::<Vec<_>>();
-
-    let state = Arc::new(Mutex::new(DHCPState::new(socket.try_clone().unwrap(), ip_pool)));
+    let state = Arc::new(Mutex::new(DhcpServer::new(socket.try_clone().unwrap(), ip_pool, config, HistoryStore::default())));
 
     let thread_state = Arc::clone(&state);
     let socket_clone = socket.try_clone().unwrap();
 
     // Thread écoute messages réseau UDP non bloquant
     thread::spawn(move || {
-        let mut buf = [0; 1024];
+        let mut buf = [0; 1500];
         loop {
             match socket_clone.recv_from(&mut buf) {
                 Ok((len, src)) => {
-                    let msg = String::from_utf8_lossy(&buf[..len]);
                     let mut st = thread_state.lock().unwrap();
-                    st.handle_message(&msg, src);
+                    st.handle_message(&buf[..len], src);
                 }
                 Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
                     // Pas de données reçues, on attend un peu pour ne pas boucler à vide
@@ -207,6 +79,19 @@ fn main() {
         }
     });
 
+    // Détection de conflits avant OFFER, hors du verrou de l'état
+    spawn_prober(&state);
+
+    // Thread de récupération des offres abandonnées et des baux expirés
+    let reaper_state = Arc::clone(&state);
+    thread::spawn(move || loop {
+        thread::sleep(Duration::from_secs(1));
+        let mut st = reaper_state.lock().unwrap();
+        st.expire_offers();
+        st.expire_leases();
+        st.expire_quarantine();
+    });
+
     // Gestion Ctrl+C (pour Linux/Unix et Windows)
     ctrlc::set_handler(move || {
         println!("\n🛑 Signal d'arrêt reçu, serveur DHCP termine proprement...");
@@ -235,10 +120,10 @@ fn main() {
             }
             "2" => {
                 let st = state.lock().unwrap();
-                st.afficher_historique();
+                afficher_historique(&st.store);
             }
             "3" => {
-                print!("Entrer l'adresse client (IP:port) à supprimer : ");
+                print!("Entrer le client à supprimer (MAC, identifiant ou IP) : ");
                 io::stdout().flush().unwrap();
                 let mut addr = String::new();
                 io::stdin().read_line(&mut addr).unwrap();
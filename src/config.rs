@@ -0,0 +1,331 @@
+use std::env;
+use std::fmt;
+use std::net::Ipv4Addr;
+use std::str::FromStr;
+
+use crate::ip_pool::{parse_ranges, AllocationStrategy, IpPool, PoolError, PoolSet, Subnet};
+use crate::message::SERVER_PORT;
+use crate::options::{self, DhcpOption};
+
+pub const DEFAULT_LEASE_TIME: u32 = 3600;
+pub const DEFAULT_OFFER_HOLD_TIME: u64 = 60;
+pub const DEFAULT_PING_TIMEOUT_MS: u64 = 500;
+pub const DEFAULT_ABANDON_TIME: u64 = 3600;
+pub const DEFAULT_DECLINE_PROBATION: u64 = 3600;
+
+/// Configuration refusée au démarrage : plutôt que de retomber sur une valeur
+/// par défaut, le serveur ne démarre pas.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    Pool(PoolError),
+    // Variable d'environnement et valeur illisible
+    InvalidVariable(&'static str, String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Pool(e) => write!(f, "{}", e),
+            ConfigError::InvalidVariable(name, value) => write!(f, "{} invalide : {}", name, value),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<PoolError> for ConfigError {
+    fn from(e: PoolError) -> Self {
+        ConfigError::Pool(e)
+    }
+}
+
+/// Sous-réseau distant desservi à travers un relais DHCP : le relais y
+/// renseigne `giaddr` avec son adresse sur ce sous-réseau (RFC 2131, 4.3.1).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayScope {
+    pub subnet: Subnet,
+    // Plages attribuables (début, fin) ; vide = tout le sous-réseau
+    pub pool_ranges: Vec<(Ipv4Addr, Ipv4Addr)>,
+    pub router: Option<Ipv4Addr>,
+    // Stratégie propre à ce pool ; à défaut, celle de DHCP_ALLOCATION
+    pub strategy: Option<AllocationStrategy>,
+}
+
+impl FromStr for RelayScope {
+    type Err = PoolError;
+
+    /// Format « sous-réseau[@passerelle][=plage,plage...][#stratégie] »,
+    /// ex: 10.30.0.0/24@10.30.0.1=10.30.0.10-10.30.0.200#random.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || PoolError::InvalidSubnet(s.trim().to_string());
+        let (spec, strategy) = match s.trim().split_once('#') {
+            Some((spec, strategy)) => (spec, Some(strategy.parse().map_err(|_| invalid())?)),
+            None => (s.trim(), None),
+        };
+        let (head, ranges) = match spec.split_once('=') {
+            Some((head, ranges)) => (head, Some(ranges)),
+            None => (spec, None),
+        };
+        let (subnet, router) = match head.split_once('@') {
+            Some((subnet, router)) => (subnet, Some(router.trim().parse().map_err(|_| invalid())?)),
+            None => (head, None),
+        };
+        // Comme pour DHCP_RANGES, « = » sans plage n'élargit pas le pool à tout le sous-réseau
+        let pool_ranges = match ranges {
+            Some(ranges) => match parse_ranges(ranges)? {
+                parsed if parsed.is_empty() => return Err(PoolError::InvalidRange(ranges.to_string())),
+                parsed => parsed,
+            },
+            None => Vec::new(),
+        };
+        Ok(RelayScope {
+            subnet: subnet.parse().map_err(|_| invalid())?,
+            pool_ranges,
+            router,
+            strategy,
+        })
+    }
+}
+
+/// Configuration du serveur : paramètres réseau annoncés aux clients et temporisations.
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub server_ip: Ipv4Addr,
+    pub subnet: Subnet,
+    // Plages attribuables (début, fin) ; vide = tout le sous-réseau
+    pub pool_ranges: Vec<(Ipv4Addr, Ipv4Addr)>,
+    pub allocation_strategy: AllocationStrategy,
+    // Plages (ou adresses seules) jamais attribuées
+    pub exclusions: Vec<(Ipv4Addr, Ipv4Addr)>,
+    pub router: Option<Ipv4Addr>,
+    pub dns_servers: Vec<Ipv4Addr>,
+    pub domain_name: Option<String>,
+    // Sous-réseaux distants, sélectionnés par le giaddr des requêtes relayées
+    pub relay_scopes: Vec<RelayScope>,
+    // Port UDP des relais, destinataires des réponses aux requêtes relayées
+    pub relay_port: u16,
+    // Durée maximale (secondes) d'un bail
+    pub lease_time: u32,
+    // Durée (secondes) pendant laquelle une adresse offerte reste réservée sans REQUEST
+    pub offer_hold_time: u64,
+    // Délai (millisecondes) du ping de détection de conflit avant OFFER ; 0 le désactive.
+    // Il retarde d'autant l'OFFER du client (une fois par adresse en conflit), pas le reste du serveur
+    pub ping_timeout_ms: u64,
+    // Durée (secondes) pendant laquelle une adresse en conflit reste hors du pool
+    pub abandon_time: u64,
+    // Durée (secondes) de quarantaine d'une adresse refusée par un client (DHCPDECLINE)
+    pub decline_probation: u64,
+}
+
+impl ServerConfig {
+    /// Configuration par défaut : le serveur fait aussi office de passerelle et de DNS.
+    pub fn new(server_ip: Ipv4Addr) -> Self {
+        ServerConfig {
+            server_ip,
+            subnet: Subnet::new(server_ip, 24).expect("préfixe /24 valide"),
+            pool_ranges: Vec::new(),
+            allocation_strategy: AllocationStrategy::default(),
+            exclusions: Vec::new(),
+            router: Some(server_ip),
+            dns_servers: Vec::new(),
+            domain_name: None,
+            relay_scopes: Vec::new(),
+            relay_port: SERVER_PORT,
+            lease_time: DEFAULT_LEASE_TIME,
+            offer_hold_time: DEFAULT_OFFER_HOLD_TIME,
+            ping_timeout_ms: DEFAULT_PING_TIMEOUT_MS,
+            abandon_time: DEFAULT_ABANDON_TIME,
+            decline_probation: DEFAULT_DECLINE_PROBATION,
+        }
+    }
+
+    /// Configuration par défaut surchargée par les variables d'environnement
+    /// DHCP_SUBNET (CIDR, ex: 10.20.0.0/22) ou DHCP_SUBNET_MASK, DHCP_RANGES
+    /// (ex: 10.20.0.10-10.20.0.200,10.20.1.1-10.20.1.50), DHCP_EXCLUDE (plages
+    /// ou adresses seules, même format), DHCP_ALLOCATION
+    /// (sequential, random, lrr ou hash ; stratégie du pool local et, par
+    /// défaut, des sous-réseaux relayés), DHCP_ROUTER, DHCP_DNS
+    /// (liste séparée par des virgules ; sans elle, pas d'option 6), DHCP_DOMAIN, DHCP_RELAY_SCOPES
+    /// (sous-réseaux relayés séparés par des points-virgules, voir `RelayScope`),
+    /// DHCP_RELAY_PORT, DHCP_LEASE_TIME, DHCP_OFFER_HOLD_TIME, DHCP_ABANDON_TIME,
+    /// DHCP_DECLINE_PROBATION (secondes) et DHCP_PING_TIMEOUT_MS.
+    /// Une variable ou un sous-réseau relayé illisible est une erreur, tout comme une liste DHCP_RANGES
+    /// vide : le pool couvrirait alors tout le sous-réseau.
+    pub fn from_env(server_ip: Ipv4Addr) -> Result<Self, ConfigError> {
+        let mut config = ServerConfig::new(server_ip);
+        if let Some(subnet) = env_var("DHCP_SUBNET")? {
+            config.subnet = subnet;
+        } else if let Some(mask) = env_var("DHCP_SUBNET_MASK")? {
+            config.subnet = Subnet::from_mask(server_ip, mask)?;
+        }
+        if let Ok(ranges) = env::var("DHCP_RANGES") {
+            config.pool_ranges = parse_ranges(&ranges)?;
+            if config.pool_ranges.is_empty() {
+                return Err(PoolError::InvalidRange(ranges).into());
+            }
+        }
+        if let Ok(exclusions) = env::var("DHCP_EXCLUDE") {
+            config.exclusions = parse_ranges(&exclusions)?;
+        }
+        if let Some(strategy) = env_var("DHCP_ALLOCATION")? {
+            config.allocation_strategy = strategy;
+        }
+        if let Some(router) = env_var("DHCP_ROUTER")? {
+            config.router = Some(router);
+        }
+        if let Ok(dns) = env::var("DHCP_DNS") {
+            config.dns_servers = dns
+                .split(',')
+                .filter(|s| !s.trim().is_empty())
+                .map(|s| s.trim().parse())
+                .collect::<Result<_, _>>()
+                .map_err(|_| ConfigError::InvalidVariable("DHCP_DNS", dns.clone()))?;
+        }
+        if let Ok(domain) = env::var("DHCP_DOMAIN") {
+            config.domain_name = Some(domain);
+        }
+        if let Ok(scopes) = env::var("DHCP_RELAY_SCOPES") {
+            config.relay_scopes = scopes
+                .split(';')
+                .filter(|scope| !scope.trim().is_empty())
+                .map(str::parse)
+                .collect::<Result<_, _>>()?;
+        }
+        if let Some(port) = env_var("DHCP_RELAY_PORT")? {
+            config.relay_port = port;
+        }
+        if let Some(lease_time) = env_var::<u32>("DHCP_LEASE_TIME")? {
+            // Un bail nul expirerait aussitôt accordé
+            if lease_time == 0 {
+                return Err(ConfigError::InvalidVariable("DHCP_LEASE_TIME", lease_time.to_string()));
+            }
+            config.lease_time = lease_time;
+        }
+        if let Some(hold) = env_var("DHCP_OFFER_HOLD_TIME")? {
+            config.offer_hold_time = hold;
+        }
+        if let Some(timeout) = env_var("DHCP_PING_TIMEOUT_MS")? {
+            config.ping_timeout_ms = timeout;
+        }
+        if let Some(abandon_time) = env_var("DHCP_ABANDON_TIME")? {
+            config.abandon_time = abandon_time;
+        }
+        if let Some(probation) = env_var("DHCP_DECLINE_PROBATION")? {
+            config.decline_probation = probation;
+        }
+        Ok(config)
+    }
+
+    /// Pools du sous-réseau local et des sous-réseaux relayés, sans l'adresse
+    /// du serveur ni celles des passerelles, et privés des exclusions configurées.
+    pub fn ip_pools(&self) -> Result<PoolSet, PoolError> {
+        let mut reserved = vec![self.server_ip];
+        reserved.extend(self.router);
+        reserved.extend(self.relay_scopes.iter().filter_map(|scope| scope.router));
+
+        let local = IpPool::new(self.subnet, &self.pool_ranges, &reserved)?.with_strategy(self.allocation_strategy);
+        let mut pools = vec![local];
+        for scope in &self.relay_scopes {
+            let strategy = scope.strategy.unwrap_or(self.allocation_strategy);
+            pools.push(IpPool::new(scope.subnet, &scope.pool_ranges, &reserved)?.with_strategy(strategy));
+        }
+        let mut pools = PoolSet::new(pools);
+        for &(start, end) in &self.exclusions {
+            pools.exclude_range(start, end);
+        }
+        Ok(pools)
+    }
+
+    /// Sous-réseau d'où provient une requête : le réseau local si elle n'est
+    /// pas relayée, sinon le sous-réseau relayé qui contient `giaddr`.
+    /// `None` pour un relais inconnu.
+    pub fn select_subnet(&self, giaddr: Ipv4Addr) -> Option<Subnet> {
+        if giaddr.is_unspecified() {
+            return Some(self.subnet);
+        }
+        self.subnet_for(giaddr)
+    }
+
+    /// Sous-réseau desservi (local ou relayé) qui contient `ip`.
+    pub fn subnet_for(&self, ip: Ipv4Addr) -> Option<Subnet> {
+        std::iter::once(self.subnet)
+            .chain(self.relay_scopes.iter().map(|scope| scope.subnet))
+            .find(|subnet| subnet.contains(ip))
+    }
+
+    // Masque et passerelle annoncés à un client d'adresse `ip`
+    fn link_settings(&self, ip: Ipv4Addr) -> (Subnet, Option<Ipv4Addr>) {
+        if !self.subnet.contains(ip) {
+            if let Some(scope) = self.relay_scopes.iter().find(|scope| scope.subnet.contains(ip)) {
+                return (scope.subnet, scope.router);
+            }
+        }
+        (self.subnet, self.router)
+    }
+
+    /// Vrai si `ip` tombe dans une exclusion configurée.
+    pub fn is_excluded(&self, ip: Ipv4Addr) -> bool {
+        self.exclusions.iter().any(|&(start, end)| start <= ip && ip <= end)
+    }
+
+    /// Durée accordée : celle demandée par le client (option 51), plafonnée
+    /// à la durée configurée. Jamais nulle, même si `lease_time` l'est.
+    pub fn grant_lease_time(&self, requested: Option<u32>) -> u32 {
+        let max = self.lease_time.max(1);
+        requested.map_or(max, |secs| secs.clamp(1, max))
+    }
+
+    /// Options de configuration réseau du sous-réseau de `ip`, limitées à celles
+    /// demandées par le client via l'option 55 quand elle est présente.
+    pub fn network_options(&self, ip: Ipv4Addr, requested: Option<&[u8]>) -> Vec<DhcpOption> {
+        let wanted = |code: u8| requested.is_none_or(|codes| codes.contains(&code));
+        let (subnet, router) = self.link_settings(ip);
+        let mut opts = Vec::new();
+        if wanted(options::SUBNET_MASK) {
+            opts.push(DhcpOption::SubnetMask(subnet.mask()));
+        }
+        if let Some(router) = router.filter(|_| wanted(options::ROUTER)) {
+            opts.push(DhcpOption::Router(vec![router]));
+        }
+        if !self.dns_servers.is_empty() && wanted(options::DOMAIN_NAME_SERVER) {
+            opts.push(DhcpOption::DomainNameServer(self.dns_servers.clone()));
+        }
+        if let Some(domain) = self.domain_name.as_ref().filter(|_| wanted(options::DOMAIN_NAME)) {
+            opts.push(DhcpOption::DomainName(domain.clone()));
+        }
+        opts
+    }
+
+    /// Options de l'ACK répondant à un DHCPINFORM : identifiant serveur et
+    /// configuration réseau, sans durée de bail (RFC 2131, 4.3.5).
+    pub fn inform_options(&self, ip: Ipv4Addr, requested: Option<&[u8]>) -> Vec<DhcpOption> {
+        let mut opts = vec![DhcpOption::ServerIdentifier(self.server_ip)];
+        opts.extend(self.network_options(ip, requested));
+        opts
+    }
+
+    /// Options d'un OFFER ou d'un ACK : identifiant serveur, durées du bail
+    /// (toujours présentes, RFC 2131 table 3) puis configuration réseau.
+    /// T1 vaut la moitié du bail et T2 les 7/8 (RFC 2131, 4.4.5).
+    pub fn lease_options(&self, lease_time: u32, ip: Ipv4Addr, requested: Option<&[u8]>) -> Vec<DhcpOption> {
+        let mut opts = vec![
+            DhcpOption::ServerIdentifier(self.server_ip),
+            DhcpOption::IpAddressLeaseTime(lease_time),
+            DhcpOption::RenewalTime(lease_time / 2),
+            DhcpOption::RebindingTime((lease_time as u64 * 7 / 8) as u32),
+        ];
+        opts.extend(self.network_options(ip, requested));
+        opts
+    }
+}
+
+// Valeur de la variable `name` si elle est définie ; une valeur illisible est une erreur
+fn env_var<T: FromStr>(name: &'static str) -> Result<Option<T>, ConfigError> {
+    match env::var(name) {
+        Ok(value) => match value.trim().parse() {
+            Ok(parsed) => Ok(Some(parsed)),
+            Err(_) => Err(ConfigError::InvalidVariable(name, value)),
+        },
+        Err(_) => Ok(None),
+    }
+}
@@ -0,0 +1,457 @@
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use rusqlite::{params, Connection};
+
+use crate::lease::{Lease, LeaseState, OFFER_EXPIRED, OFFER_WITHDRAWN};
+use crate::options::{decode_options, encode_options};
+use crate::reservation::Reservation;
+use crate::store::LeaseStore;
+
+// Format des dates en base, identique à CURRENT_TIMESTAMP (UTC)
+const DB_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+// Écriture différée en base, exécutée par le thread d'écriture
+type DbJob = Box<dyn FnOnce(&Connection) -> rusqlite::Result<()> + Send>;
+
+/// Ouvre (ou crée) la base SQLite du serveur.
+pub fn open(path: &str) -> rusqlite::Result<Connection> {
+    let conn = Connection::open(path)?;
+    init_db(&conn)?;
+    close_stale_offers(&conn)?;
+    Ok(conn)
+}
+
+/// Crée les tables manquantes et complète celles des versions précédentes.
+pub fn init_db(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS leases (
+            id INTEGER PRIMARY KEY,
+            mac TEXT NOT NULL,
+            ip TEXT NOT NULL,
+            start_time DATETIME DEFAULT CURRENT_TIMESTAMP,
+            end_time DATETIME,
+            vendor TEXT,
+            status TEXT,
+            client_id TEXT,
+            renewals INTEGER NOT NULL DEFAULT 0,
+            renewed_at DATETIME,
+            circuit_id TEXT,
+            remote_id TEXT
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS reservations (
+            id INTEGER PRIMARY KEY,
+            mac TEXT,
+            client_id TEXT,
+            circuit_id TEXT,
+            remote_id TEXT,
+            ip TEXT NOT NULL UNIQUE,
+            hostname TEXT,
+            options BLOB
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS exclusions (
+            id INTEGER PRIMARY KEY,
+            start_ip TEXT NOT NULL,
+            end_ip TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // Bases créées par les versions précédentes : colonnes ajoutées depuis
+    for (table, column, definition) in [
+        ("leases", "client_id", "TEXT"),
+        ("leases", "renewals", "INTEGER NOT NULL DEFAULT 0"),
+        ("leases", "renewed_at", "DATETIME"),
+        ("leases", "circuit_id", "TEXT"),
+        ("leases", "remote_id", "TEXT"),
+        ("reservations", "circuit_id", "TEXT"),
+        ("reservations", "remote_id", "TEXT"),
+    ] {
+        let exists = conn
+            .prepare("SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2")?
+            .exists([table, column])?;
+        if !exists {
+            conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+        }
+    }
+    Ok(())
+}
+
+/// Clôt les offres restées en suspens lors du dernier arrêt : aucune n'a survécu
+/// au redémarrage, elles sont enregistrées comme expirées. Renvoie leur nombre.
+pub fn close_stale_offers(conn: &Connection) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE leases SET end_time = CURRENT_TIMESTAMP, status = ?1
+         WHERE status = ?2 AND end_time IS NULL",
+        params![OFFER_EXPIRED, LeaseState::Offered.as_str()],
+    )
+}
+
+/// Recharge les baux confirmés encore valides, le plus récent l'emportant pour chaque client.
+pub fn load_active_leases(conn: &Connection) -> rusqlite::Result<Vec<Lease>> {
+    let mut stmt = conn.prepare(
+        "SELECT COALESCE(client_id, mac), mac, ip, end_time FROM leases
+         WHERE status IN (?1, ?2) AND end_time > CURRENT_TIMESTAMP
+         ORDER BY id",
+    )?;
+    let rows = stmt.query_map(
+        params![LeaseState::Bound.as_str(), LeaseState::Renewing.as_str()],
+        |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        },
+    )?;
+
+    let mut leases: HashMap<String, Lease> = HashMap::new();
+    for (client_id, mac, ip, end) in rows.flatten() {
+        let ip = match ip.parse() {
+            Ok(ip) => ip,
+            Err(_) => continue,
+        };
+        let end_time = match NaiveDateTime::parse_from_str(&end, DB_TIME_FORMAT) {
+            Ok(end) => end.and_utc(),
+            Err(_) => continue,
+        };
+        leases.insert(client_id.clone(), Lease::restore(&client_id, &mac, ip, end_time));
+    }
+    Ok(leases.into_values().collect())
+}
+
+/// Dernière adresse effectivement détenue par chaque client (identifiant et MAC),
+/// d'après l'historique des baux.
+pub fn load_previous_ips(conn: &Connection) -> rusqlite::Result<HashMap<String, Ipv4Addr>> {
+    let mut stmt = conn.prepare(
+        "SELECT COALESCE(client_id, mac), mac, ip FROM leases
+         WHERE status NOT IN (?1, ?2, ?3, ?4, ?5)
+         ORDER BY id",
+    )?;
+    let rows = stmt.query_map(
+        params![
+            LeaseState::Offered.as_str(),
+            OFFER_EXPIRED,
+            OFFER_WITHDRAWN,
+            LeaseState::Declined.as_str(),
+            LeaseState::Abandoned.as_str(),
+        ],
+        |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)),
+    )?;
+
+    let mut previous_ips = HashMap::new();
+    for (client_id, mac, ip) in rows.flatten() {
+        if let Ok(ip) = ip.parse() {
+            previous_ips.insert(mac, ip);
+            previous_ips.insert(client_id, ip);
+        }
+    }
+    Ok(previous_ips)
+}
+
+/// Adresses encore en quarantaine : refusées par DHCPDECLINE depuis moins de
+/// `decline_probation` secondes ou abandonnées après un ping depuis moins de
+/// `abandon_time` secondes. Renvoie chaque adresse avec le délai restant.
+pub fn load_quarantine(conn: &Connection, decline_probation: u64, abandon_time: u64) -> rusqlite::Result<Vec<(Ipv4Addr, u64)>> {
+    let mut stmt = conn.prepare(
+        "SELECT ip, status, COALESCE(end_time, start_time) FROM leases
+         WHERE status IN (?1, ?2)
+         ORDER BY id",
+    )?;
+    let declined = LeaseState::Declined.as_str();
+    let rows = stmt.query_map(
+        params![declined, LeaseState::Abandoned.as_str()],
+        |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)),
+    )?;
+
+    let now = Utc::now();
+    let mut quarantine = HashMap::new();
+    for (ip, status, since) in rows.flatten() {
+        let (ip, since) = match (ip.parse(), NaiveDateTime::parse_from_str(&since, DB_TIME_FORMAT)) {
+            (Ok(ip), Ok(since)) => (ip, since.and_utc()),
+            _ => continue,
+        };
+        let secs = if status == declined { decline_probation } else { abandon_time };
+        let remaining = (since + TimeDelta::seconds(secs as i64) - now).num_seconds();
+        if remaining > 0 {
+            quarantine.insert(ip, remaining as u64);
+        }
+    }
+    Ok(quarantine.into_iter().collect())
+}
+
+/// Charge les réservations statiques ; les options supplémentaires sont stockées encodées (TLV).
+pub fn load_reservations(conn: &Connection) -> rusqlite::Result<Vec<Reservation>> {
+    let mut stmt = conn.prepare(
+        "SELECT mac, client_id, circuit_id, remote_id, ip, hostname, options FROM reservations ORDER BY id",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, Option<String>>(0)?,
+            row.get::<_, Option<String>>(1)?,
+            row.get::<_, Option<String>>(2)?,
+            row.get::<_, Option<String>>(3)?,
+            row.get::<_, String>(4)?,
+            row.get::<_, Option<String>>(5)?,
+            row.get::<_, Option<Vec<u8>>>(6)?,
+        ))
+    })?;
+
+    let mut reservations = Vec::new();
+    for (mac, client_id, circuit_id, remote_id, ip, hostname, raw_options) in rows.flatten() {
+        let ip = match ip.parse() {
+            Ok(ip) => ip,
+            Err(_) => {
+                eprintln!("⚠️ Réservation ignorée, adresse invalide : {}", ip);
+                continue;
+            }
+        };
+        let options = decode_options(&raw_options.unwrap_or_default()).unwrap_or_else(|e| {
+            eprintln!("⚠️ Options invalides pour la réservation {} : {}", ip, e);
+            Vec::new()
+        });
+        reservations.push(Reservation { mac, client_id, circuit_id, remote_id, ip, hostname, options });
+    }
+    Ok(reservations)
+}
+
+fn insert_reservation(conn: &Connection, reservation: &Reservation) -> rusqlite::Result<()> {
+    let mut raw_options = Vec::new();
+    if !reservation.options.is_empty() {
+        encode_options(&reservation.options, &mut raw_options);
+    }
+    conn.execute(
+        "INSERT INTO reservations (mac, client_id, circuit_id, remote_id, ip, hostname, options)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            reservation.mac,
+            reservation.client_id,
+            reservation.circuit_id,
+            reservation.remote_id,
+            reservation.ip.to_string(),
+            reservation.hostname,
+            (!raw_options.is_empty()).then_some(raw_options),
+        ],
+    )?;
+    Ok(())
+}
+
+fn delete_reservation(conn: &Connection, ip: Ipv4Addr) -> rusqlite::Result<()> {
+    conn.execute("DELETE FROM reservations WHERE ip = ?1", params![ip.to_string()])?;
+    Ok(())
+}
+
+/// Charge les plages exclues de l'attribution dynamique.
+pub fn load_exclusions(conn: &Connection) -> rusqlite::Result<Vec<(Ipv4Addr, Ipv4Addr)>> {
+    let mut stmt = conn.prepare("SELECT start_ip, end_ip FROM exclusions ORDER BY id")?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+    Ok(rows
+        .flatten()
+        .filter_map(|(start, end)| Some((start.parse().ok()?, end.parse().ok()?)))
+        .collect())
+}
+
+fn insert_exclusion(conn: &Connection, (start, end): (Ipv4Addr, Ipv4Addr)) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO exclusions (start_ip, end_ip) VALUES (?1, ?2)",
+        params![start.to_string(), end.to_string()],
+    )?;
+    Ok(())
+}
+
+fn delete_exclusion(conn: &Connection, (start, end): (Ipv4Addr, Ipv4Addr)) -> rusqlite::Result<()> {
+    conn.execute(
+        "DELETE FROM exclusions WHERE start_ip = ?1 AND end_ip = ?2",
+        params![start.to_string(), end.to_string()],
+    )?;
+    Ok(())
+}
+
+// Circuit et relais (option 82) par lesquels le client a été vu en dernier
+fn relay_ids(lease: &Lease) -> (Option<String>, Option<String>) {
+    match &lease.relay_agent {
+        Some(info) => (info.circuit_id(), info.remote_id()),
+        None => (None, None),
+    }
+}
+
+// Enregistre un nouveau bail (offre ou adresse abandonnée)
+fn log_lease(conn: &Connection, lease: &Lease, vendor: &str) -> rusqlite::Result<()> {
+    let (circuit_id, remote_id) = relay_ids(lease);
+    conn.execute(
+        "INSERT INTO leases (mac, ip, vendor, status, client_id, circuit_id, remote_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![lease.mac, lease.ip.to_string(), vendor, lease.state.as_str(), lease.client_id, circuit_id, remote_id],
+    )?;
+    Ok(())
+}
+
+// Confirme un bail et enregistre sa date de fin ; crée la ligne si l'offre n'a pas été enregistrée
+fn acknowledge_lease(conn: &Connection, lease: &Lease, vendor: &str) -> rusqlite::Result<()> {
+    let end_time = lease.end_time.map(|end| end.format(DB_TIME_FORMAT).to_string());
+    let (circuit_id, remote_id) = relay_ids(lease);
+    let [offered, bound, renewing] = LeaseState::active_states().map(LeaseState::as_str);
+    let updated = conn.execute(
+        "UPDATE leases SET end_time = ?1, status = ?2,
+             circuit_id = COALESCE(?8, circuit_id), remote_id = COALESCE(?9, remote_id)
+         WHERE mac = ?3 AND ip = ?4 AND status IN (?5, ?6, ?7)",
+        params![
+            end_time,
+            lease.state.as_str(),
+            lease.mac,
+            lease.ip.to_string(),
+            offered,
+            bound,
+            renewing,
+            circuit_id,
+            remote_id,
+        ],
+    )?;
+    if updated == 0 {
+        conn.execute(
+            "INSERT INTO leases (mac, ip, vendor, status, end_time, client_id, circuit_id, remote_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                lease.mac,
+                lease.ip.to_string(),
+                vendor,
+                lease.state.as_str(),
+                end_time,
+                lease.client_id,
+                circuit_id,
+                remote_id,
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+// Prolonge un bail confirmé (RENEWING, REBINDING ou INIT-REBOOT) et compte le renouvellement
+fn renew_lease(conn: &Connection, lease: &Lease, vendor: &str) -> rusqlite::Result<()> {
+    let end_time = lease.end_time.map(|end| end.format(DB_TIME_FORMAT).to_string());
+    let (circuit_id, remote_id) = relay_ids(lease);
+    let [offered, bound, renewing] = LeaseState::active_states().map(LeaseState::as_str);
+    let updated = conn.execute(
+        "UPDATE leases SET end_time = ?1, status = ?2, renewals = renewals + 1, renewed_at = CURRENT_TIMESTAMP,
+             circuit_id = COALESCE(?8, circuit_id), remote_id = COALESCE(?9, remote_id)
+         WHERE mac = ?3 AND ip = ?4 AND status IN (?5, ?6, ?7)",
+        params![
+            end_time,
+            lease.state.as_str(),
+            lease.mac,
+            lease.ip.to_string(),
+            offered,
+            bound,
+            renewing,
+            circuit_id,
+            remote_id,
+        ],
+    )?;
+    if updated == 0 {
+        acknowledge_lease(conn, lease, vendor)?;
+    }
+    Ok(())
+}
+
+// Clôt un bail en cours (offert ou confirmé) avec le statut final donné
+fn update_lease_status(conn: &Connection, lease: &Lease, status: &str) -> rusqlite::Result<()> {
+    let [offered, bound, renewing] = LeaseState::active_states().map(LeaseState::as_str);
+    conn.execute(
+        "UPDATE leases SET end_time = CURRENT_TIMESTAMP, status = ?1
+         WHERE mac = ?2 AND ip = ?3 AND status IN (?4, ?5, ?6)",
+        params![status, lease.mac, lease.ip.to_string(), offered, bound, renewing],
+    )?;
+    Ok(())
+}
+
+/// Persistance SQLite. Les écritures sont confiées à un thread dédié qui les
+/// applique dans l'ordre des événements (un REQUEST rapide ne peut pas être
+/// enregistré avant l'OFFER correspondant) sans bloquer le traitement réseau.
+pub struct SqliteStore {
+    pub conn: Arc<Mutex<Connection>>,
+    tx: Sender<DbJob>,
+}
+
+impl SqliteStore {
+    pub fn new(conn: Connection) -> Self {
+        let conn = Arc::new(Mutex::new(conn));
+        let (tx, rx) = mpsc::channel::<DbJob>();
+        let writer_conn = Arc::clone(&conn);
+        thread::spawn(move || {
+            for job in rx {
+                let conn = writer_conn.lock().unwrap();
+                job(&conn).unwrap_or_else(|e| eprintln!("Erreur DB: {}", e));
+            }
+        });
+        SqliteStore { conn, tx }
+    }
+
+    fn record<F>(&self, job: F)
+    where
+        F: FnOnce(&Connection) -> rusqlite::Result<()> + Send + 'static,
+    {
+        let _ = self.tx.send(Box::new(job));
+    }
+
+    /// Attend que les écritures déjà confiées au thread d'écriture soient appliquées.
+    pub fn flush(&self) {
+        let (done_tx, done_rx) = mpsc::channel();
+        self.record(move |_| {
+            let _ = done_tx.send(());
+            Ok(())
+        });
+        let _ = done_rx.recv();
+    }
+}
+
+impl LeaseStore for SqliteStore {
+    fn lease_created(&mut self, lease: &Lease, vendor: &str) {
+        let (lease, vendor) = (lease.clone(), vendor.to_string());
+        self.record(move |conn| log_lease(conn, &lease, &vendor));
+    }
+
+    fn lease_bound(&mut self, lease: &Lease, vendor: &str) {
+        let (lease, vendor) = (lease.clone(), vendor.to_string());
+        self.record(move |conn| acknowledge_lease(conn, &lease, &vendor));
+    }
+
+    fn lease_renewed(&mut self, lease: &Lease, vendor: &str) {
+        let (lease, vendor) = (lease.clone(), vendor.to_string());
+        self.record(move |conn| renew_lease(conn, &lease, &vendor));
+    }
+
+    fn lease_closed(&mut self, lease: &Lease, status: &'static str) {
+        let lease = lease.clone();
+        self.record(move |conn| update_lease_status(conn, &lease, status));
+    }
+
+    fn reservation_added(&mut self, reservation: &Reservation) {
+        let reservation = reservation.clone();
+        self.record(move |conn| insert_reservation(conn, &reservation));
+    }
+
+    fn reservation_removed(&mut self, ip: Ipv4Addr) {
+        self.record(move |conn| delete_reservation(conn, ip));
+    }
+
+    fn exclusion_added(&mut self, range: (Ipv4Addr, Ipv4Addr)) {
+        self.record(move |conn| insert_exclusion(conn, range));
+    }
+
+    fn exclusion_removed(&mut self, range: (Ipv4Addr, Ipv4Addr)) {
+        self.record(move |conn| delete_exclusion(conn, range));
+    }
+}
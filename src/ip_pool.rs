@@ -1,48 +1,579 @@
+use std::collections::VecDeque;
+use std::fmt;
 use std::net::Ipv4Addr;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+/// Sous-réseau IPv4 au format CIDR (ex: 10.20.0.0/22).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Subnet {
+    network: Ipv4Addr,
+    prefix_len: u8,
+}
+
+impl Subnet {
+    /// Les bits d'hôte de `addr` sont ignorés : 10.20.1.7/22 donne 10.20.0.0/22.
+    pub fn new(addr: Ipv4Addr, prefix_len: u8) -> Result<Self, PoolError> {
+        if prefix_len > 32 {
+            return Err(PoolError::InvalidSubnet(format!("{}/{}", addr, prefix_len)));
+        }
+        let mask = prefix_mask(prefix_len);
+        Ok(Subnet {
+            network: Ipv4Addr::from(u32::from(addr) & mask),
+            prefix_len,
+        })
+    }
+
+    /// Sous-réseau de `addr` décrit par un masque (ex: 255.255.252.0).
+    pub fn from_mask(addr: Ipv4Addr, mask: Ipv4Addr) -> Result<Self, PoolError> {
+        let bits = u32::from(mask);
+        // Un masque valide est une suite de 1 suivie de 0
+        if bits.leading_ones() + bits.trailing_zeros() != 32 {
+            return Err(PoolError::InvalidSubnet(format!("{} masque {}", addr, mask)));
+        }
+        Subnet::new(addr, bits.leading_ones() as u8)
+    }
+
+    pub fn network(&self) -> Ipv4Addr {
+        self.network
+    }
+
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    pub fn mask(&self) -> Ipv4Addr {
+        Ipv4Addr::from(prefix_mask(self.prefix_len))
+    }
+
+    pub fn broadcast(&self) -> Ipv4Addr {
+        Ipv4Addr::from(u32::from(self.network) | !prefix_mask(self.prefix_len))
+    }
+
+    pub fn contains(&self, ip: Ipv4Addr) -> bool {
+        u32::from(ip) & prefix_mask(self.prefix_len) == u32::from(self.network)
+    }
+
+    /// Première et dernière adresses attribuables. Les /31 et /32 n'ont ni
+    /// adresse réseau ni adresse de broadcast (RFC 3021).
+    pub fn host_range(&self) -> (Ipv4Addr, Ipv4Addr) {
+        let (network, broadcast) = (u32::from(self.network), u32::from(self.broadcast()));
+        if self.prefix_len >= 31 {
+            (Ipv4Addr::from(network), Ipv4Addr::from(broadcast))
+        } else {
+            (Ipv4Addr::from(network + 1), Ipv4Addr::from(broadcast - 1))
+        }
+    }
+}
+
+fn prefix_mask(prefix_len: u8) -> u32 {
+    u32::MAX.checked_shl(32 - prefix_len as u32).unwrap_or(0)
+}
+
+impl fmt::Display for Subnet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix_len)
+    }
+}
+
+impl FromStr for Subnet {
+    type Err = PoolError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || PoolError::InvalidSubnet(s.to_string());
+        let (addr, prefix_len) = s.trim().split_once('/').ok_or_else(invalid)?;
+        let addr = addr.parse().map_err(|_| invalid())?;
+        let prefix_len = prefix_len.parse().map_err(|_| invalid())?;
+        Subnet::new(addr, prefix_len)
+    }
+}
+
+/// Plage d'adresses « début-fin » (ex: 10.20.0.10-10.20.0.200) ou adresse seule.
+pub fn parse_range(s: &str) -> Option<(Ipv4Addr, Ipv4Addr)> {
+    match s.trim().split_once('-') {
+        Some((start, end)) => Some((start.trim().parse().ok()?, end.trim().parse().ok()?)),
+        None => {
+            let ip = s.trim().parse().ok()?;
+            Some((ip, ip))
+        }
+    }
+}
+
+/// Liste de plages séparées par des virgules (format de `parse_range`). Une
+/// entrée illisible est une erreur : l'ignorer élargirait le pool en silence.
+pub fn parse_ranges(list: &str) -> Result<Vec<(Ipv4Addr, Ipv4Addr)>, PoolError> {
+    list.split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .map(|entry| parse_range(entry).ok_or_else(|| PoolError::InvalidRange(entry.trim().to_string())))
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PoolError {
+    InvalidSubnet(String),
+    InvalidRange(String),
+    InvertedRange(Ipv4Addr, Ipv4Addr),
+    RangeOutsideSubnet(Ipv4Addr, Ipv4Addr),
+}
+
+impl fmt::Display for PoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PoolError::InvalidSubnet(s) => write!(f, "sous-réseau invalide : {}", s),
+            PoolError::InvalidRange(s) => write!(f, "plage invalide : {}", s),
+            PoolError::InvertedRange(start, end) => write!(f, "plage inversée : {}-{}", start, end),
+            PoolError::RangeOutsideSubnet(start, end) => {
+                write!(f, "plage {}-{} hors du sous-réseau", start, end)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PoolError {}
+
+/// Ordre dans lequel le pool choisit une adresse libre.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AllocationStrategy {
+    /// Plus basse adresse libre : attributions stables et prévisibles.
+    #[default]
+    Sequential,
+    /// Adresse libre tirée au hasard (réseaux invités).
+    Random,
+    /// Adresses jamais attribuées d'abord, puis celles libérées depuis le plus longtemps.
+    LeastRecentlyReleased,
+    /// Position dérivée de l'adresse MAC : un client retombe sur la même adresse tant qu'elle est libre.
+    HashOfMac,
+}
+
+impl AllocationStrategy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AllocationStrategy::Sequential => "sequential",
+            AllocationStrategy::Random => "random",
+            AllocationStrategy::LeastRecentlyReleased => "lrr",
+            AllocationStrategy::HashOfMac => "hash",
+        }
+    }
+}
+
+impl fmt::Display for AllocationStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for AllocationStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "sequential" => Ok(AllocationStrategy::Sequential),
+            "random" => Ok(AllocationStrategy::Random),
+            "lrr" | "least-recently-released" => Ok(AllocationStrategy::LeastRecentlyReleased),
+            "hash" | "hash-of-mac" => Ok(AllocationStrategy::HashOfMac),
+            other => Err(format!("stratégie d'allocation inconnue : {}", other)),
+        }
+    }
+}
+
+// FNV-1a 64 bits : pas de dépendance de hachage, et stable d'un redémarrage à l'autre
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+const WORD_BITS: usize = 64;
+
+/// Pool d'adresses sous forme de bitmap : un bit par adresse entre la plus
+/// basse et la plus haute adresse attribuable. Un second niveau signale les mots
+/// contenant encore une adresse libre, pour trouver la suivante sans parcourir
+/// tout le pool (1 mot de résumé couvre 4096 adresses).
 pub struct IpPool {
-    available_ips: Vec<Ipv4Addr>,
-    leased_ips: Vec<Ipv4Addr>,
+    subnet: Subnet,
+    // Première adresse couverte par les bitmaps et nombre d'adresses couvertes
+    base: u32,
+    span: usize,
+    // Adresses appartenant au pool (hors réseau, broadcast et réservées)
+    members: Vec<u64>,
+    // Adresses du pool retirées de l'attribution dynamique (réservations, exclusions)
+    excluded: Vec<u64>,
+    // Adresses du pool actuellement libres
+    free: Vec<u64>,
+    // Bit i à 1 si free[i] contient au moins une adresse libre
+    summary: Vec<u64>,
+    available: usize,
+    strategy: AllocationStrategy,
+    // État du générateur xorshift (stratégie Random)
+    rng: u64,
+    // Stratégie LeastRecentlyReleased : adresses déjà attribuées au moins une fois,
+    // curseur des adresses jamais attribuées et file des libérations
+    used: Vec<u64>,
+    fresh_cursor: usize,
+    released: VecDeque<usize>,
 }
 
 impl IpPool {
-    pub fn new(start: u8, end: u8) -> Self {
-        let base = [192, 168, 1, 0];
-        let mut available_ips = Vec::new();
-        for i in start..=end {
-            available_ips.push(Ipv4Addr::new(base[0], base[1], base[2], i));
+    /// Pool couvrant les plages `ranges` du sous-réseau (tout le sous-réseau si
+    /// aucune plage n'est donnée). Les adresses réseau et broadcast ainsi que
+    /// les adresses `reserved` (serveur, passerelle) ne sont jamais attribuées.
+    pub fn new(subnet: Subnet, ranges: &[(Ipv4Addr, Ipv4Addr)], reserved: &[Ipv4Addr]) -> Result<Self, PoolError> {
+        let (first_host, last_host) = subnet.host_range();
+        let whole_subnet = [(first_host, last_host)];
+        let ranges = if ranges.is_empty() { &whole_subnet[..] } else { ranges };
+
+        // Plages ramenées aux adresses attribuables du sous-réseau
+        let mut bounds = Vec::with_capacity(ranges.len());
+        for &(start, end) in ranges {
+            if start > end {
+                return Err(PoolError::InvertedRange(start, end));
+            }
+            if !subnet.contains(start) || !subnet.contains(end) {
+                return Err(PoolError::RangeOutsideSubnet(start, end));
+            }
+            let start = u32::from(start).max(u32::from(first_host));
+            let end = u32::from(end).min(u32::from(last_host));
+            if start <= end {
+                bounds.push((start, end));
+            }
+        }
+
+        let base = bounds.iter().map(|&(start, _)| start).min().unwrap_or(0);
+        let span = bounds.iter().map(|&(_, end)| (end - base) as usize + 1).max().unwrap_or(0);
+        let words = span.div_ceil(WORD_BITS);
+        let mut pool = IpPool {
+            subnet,
+            base,
+            span,
+            members: vec![0; words],
+            excluded: vec![0; words],
+            free: vec![0; words],
+            summary: vec![0; words.div_ceil(WORD_BITS)],
+            available: 0,
+            strategy: AllocationStrategy::default(),
+            rng: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or(0)
+                | 1,
+            used: vec![0; words],
+            fresh_cursor: 0,
+            released: VecDeque::new(),
+        };
+
+        for (start, end) in bounds {
+            for ip in start..=end {
+                let bit = (ip - base) as usize;
+                pool.members[bit / WORD_BITS] |= 1 << (bit % WORD_BITS);
+            }
+        }
+        for ip in reserved {
+            if let Some(bit) = pool.bit(*ip) {
+                pool.members[bit / WORD_BITS] &= !(1 << (bit % WORD_BITS));
+            }
         }
-        IpPool {
-            available_ips,
-            leased_ips: Vec::new(),
+        for word in 0..words {
+            pool.free[word] = pool.members[word];
+            pool.available += pool.members[word].count_ones() as usize;
+            pool.update_summary(word);
         }
+        Ok(pool)
     }
 
+    pub fn with_strategy(mut self, strategy: AllocationStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    pub fn strategy(&self) -> AllocationStrategy {
+        self.strategy
+    }
+
+    pub fn subnet(&self) -> Subnet {
+        self.subnet
+    }
+
+    /// Nombre d'adresses libres.
+    pub fn available(&self) -> usize {
+        self.available
+    }
+
+    /// Attribue une adresse libre selon la stratégie du pool, sans client
+    /// particulier (HashOfMac se comporte alors comme Sequential).
     pub fn lease_ip(&mut self) -> Option<Ipv4Addr> {
-        if let Some(ip) = self.available_ips.pop() {
-            self.leased_ips.push(ip);
-            Some(ip)
-        } else {
-            None
+        self.allocate(None)
+    }
+
+    /// Attribue une adresse libre au client d'adresse MAC `mac`.
+    pub fn lease_ip_for(&mut self, mac: &str) -> Option<Ipv4Addr> {
+        self.allocate(Some(mac))
+    }
+
+    fn allocate(&mut self, mac: Option<&str>) -> Option<Ipv4Addr> {
+        let span = self.span;
+        let bit = match (self.strategy, mac) {
+            (AllocationStrategy::Random, _) if span > 0 => {
+                let start = (self.next_random() % span as u64) as usize;
+                self.next_free_from(start)
+            }
+            (AllocationStrategy::HashOfMac, Some(mac)) if span > 0 => {
+                let start = (fnv1a(mac.as_bytes()) % span as u64) as usize;
+                self.next_free_from(start)
+            }
+            (AllocationStrategy::LeastRecentlyReleased, _) => self.least_recently_released(),
+            _ => self.next_free_from(0),
+        }?;
+        self.set_free(bit, false);
+        Some(self.addr(bit))
+    }
+
+    // Première adresse libre à partir de `start`, en revenant au début du pool si besoin
+    fn next_free_from(&self, start: usize) -> Option<usize> {
+        let word = start / WORD_BITS;
+        let masked = self.free.get(word)? & (u64::MAX << (start % WORD_BITS));
+        if masked != 0 {
+            return Some(word * WORD_BITS + masked.trailing_zeros() as usize);
+        }
+        let word = self.next_free_word(word + 1).or_else(|| self.next_free_word(0))?;
+        Some(word * WORD_BITS + self.free[word].trailing_zeros() as usize)
+    }
+
+    // Premier mot contenant une adresse libre à partir du mot `from`, via le résumé
+    fn next_free_word(&self, from: usize) -> Option<usize> {
+        if from >= self.free.len() {
+            return None;
+        }
+        let index = from / WORD_BITS;
+        let masked = self.summary[index] & (u64::MAX << (from % WORD_BITS));
+        if masked != 0 {
+            return Some(index * WORD_BITS + masked.trailing_zeros() as usize);
+        }
+        self.summary[index + 1..]
+            .iter()
+            .position(|&w| w != 0)
+            .map(|offset| {
+                let index = index + 1 + offset;
+                index * WORD_BITS + self.summary[index].trailing_zeros() as usize
+            })
+    }
+
+    fn least_recently_released(&mut self) -> Option<usize> {
+        // Les adresses jamais attribuées sont les plus anciennement libres
+        while self.fresh_cursor < self.span {
+            let bit = self.fresh_cursor;
+            if self.is_free(bit) && self.used[bit / WORD_BITS] & (1 << (bit % WORD_BITS)) == 0 {
+                return Some(bit);
+            }
+            self.fresh_cursor += 1;
         }
+        // Entrées périmées : adresse reprise entre-temps par `take_ip` ou `confirm_lease`
+        while let Some(bit) = self.released.pop_front() {
+            if self.is_free(bit) {
+                return Some(bit);
+            }
+        }
+        self.next_free_from(0)
+    }
+
+    fn next_random(&mut self) -> u64 {
+        // xorshift64
+        let mut x = self.rng;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.rng = x;
+        x
+    }
+
+    /// Vrai si `ip` relève de l'attribution dynamique (plages du pool, hors exclusions).
+    pub fn contains(&self, ip: Ipv4Addr) -> bool {
+        self.member_bit(ip).is_some()
     }
 
+    /// Attribue une adresse précise si elle est libre.
+    pub fn take_ip(&mut self, ip: Ipv4Addr) -> bool {
+        match self.member_bit(ip) {
+            Some(bit) if self.is_free(bit) => {
+                self.set_free(bit, false);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Réserve une adresse précise ; faux si elle n'appartient pas au pool.
     pub fn confirm_lease(&mut self, ip: Ipv4Addr) -> bool {
-        if self.leased_ips.contains(&ip) {
-            true
-        } else if self.available_ips.contains(&ip) {
-            self.available_ips.retain(|&x| x != ip);
-            self.leased_ips.push(ip);
-            true
+        match self.member_bit(ip) {
+            Some(bit) => {
+                if self.is_free(bit) {
+                    self.set_free(bit, false);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn release_ip(&mut self, ip: &Ipv4Addr) {
+        if let Some(bit) = self.member_bit(*ip) {
+            if !self.is_free(bit) {
+                self.set_free(bit, true);
+                if self.strategy == AllocationStrategy::LeastRecentlyReleased {
+                    self.released.push_back(bit);
+                }
+            }
+        }
+    }
+
+    /// Retire une adresse de l'attribution dynamique. Si elle est louée, elle
+    /// ne reviendra pas dans le pool à sa libération.
+    pub fn exclude(&mut self, ip: Ipv4Addr) {
+        if let Some(bit) = self.member_bit(ip) {
+            if self.is_free(bit) {
+                self.set_free(bit, false);
+            }
+            self.excluded[bit / WORD_BITS] |= 1 << (bit % WORD_BITS);
+        }
+    }
+
+    /// Retire toutes les adresses de `start` à `end` de l'attribution dynamique.
+    pub fn exclude_range(&mut self, start: Ipv4Addr, end: Ipv4Addr) {
+        if self.span == 0 {
+            return;
+        }
+        // Seule la partie couverte par le pool compte
+        let start = u32::from(start).max(self.base);
+        let end = u32::from(end).min(self.base + (self.span - 1) as u32);
+        for ip in start..=end {
+            self.exclude(Ipv4Addr::from(ip));
+        }
+    }
+
+    /// Rend une adresse exclue à l'attribution dynamique. L'appelant la
+    /// confirme ensuite (`confirm_lease`) si un bail l'occupe encore.
+    pub fn include(&mut self, ip: Ipv4Addr) {
+        if let Some(bit) = self.bit(ip) {
+            let mask = 1 << (bit % WORD_BITS);
+            if self.excluded[bit / WORD_BITS] & mask != 0 {
+                self.excluded[bit / WORD_BITS] &= !mask;
+                self.set_free(bit, true);
+            }
+        }
+    }
+
+    // Position de `ip` dans les bitmaps, si elle est couverte
+    fn bit(&self, ip: Ipv4Addr) -> Option<usize> {
+        let bit = u32::from(ip).checked_sub(self.base)? as usize;
+        (bit < self.members.len() * WORD_BITS).then_some(bit)
+    }
+
+    // Position de `ip` si elle appartient au pool et n'est pas exclue
+    fn member_bit(&self, ip: Ipv4Addr) -> Option<usize> {
+        self.bit(ip).filter(|bit| {
+            let mask = 1 << (bit % WORD_BITS);
+            self.members[bit / WORD_BITS] & mask != 0 && self.excluded[bit / WORD_BITS] & mask == 0
+        })
+    }
+
+    fn addr(&self, bit: usize) -> Ipv4Addr {
+        Ipv4Addr::from(self.base + bit as u32)
+    }
+
+    fn is_free(&self, bit: usize) -> bool {
+        self.free[bit / WORD_BITS] & (1 << (bit % WORD_BITS)) != 0
+    }
+
+    fn set_free(&mut self, bit: usize, free: bool) {
+        let word = bit / WORD_BITS;
+        if free {
+            self.free[word] |= 1 << (bit % WORD_BITS);
+            self.available += 1;
+        } else {
+            self.free[word] &= !(1 << (bit % WORD_BITS));
+            self.used[word] |= 1 << (bit % WORD_BITS);
+            self.available -= 1;
+        }
+        self.update_summary(word);
+    }
+
+    fn update_summary(&mut self, word: usize) {
+        let mask = 1 << (word % WORD_BITS);
+        if self.free[word] != 0 {
+            self.summary[word / WORD_BITS] |= mask;
         } else {
-            false
+            self.summary[word / WORD_BITS] &= !mask;
         }
     }
+}
+
+/// Pools de plusieurs sous-réseaux : le réseau local et ceux desservis à
+/// travers des relais. Chaque adresse est confiée au pool dont le sous-réseau
+/// la contient ; une adresse hors de tous les sous-réseaux est ignorée.
+pub struct PoolSet {
+    pools: Vec<IpPool>,
+}
+
+impl PoolSet {
+    pub fn new(pools: Vec<IpPool>) -> Self {
+        PoolSet { pools }
+    }
+
+    pub fn pools(&self) -> &[IpPool] {
+        &self.pools
+    }
+
+    /// Nombre d'adresses libres, tous sous-réseaux confondus.
+    pub fn available(&self) -> usize {
+        self.pools.iter().map(IpPool::available).sum()
+    }
+
+    /// Attribue une adresse libre du sous-réseau `subnet` au client d'adresse MAC `mac`.
+    pub fn lease_ip_for(&mut self, subnet: Subnet, mac: &str) -> Option<Ipv4Addr> {
+        self.pools.iter_mut().find(|pool| pool.subnet() == subnet)?.lease_ip_for(mac)
+    }
+
+    pub fn contains(&self, ip: Ipv4Addr) -> bool {
+        self.pool(ip).is_some_and(|pool| pool.contains(ip))
+    }
+
+    pub fn take_ip(&mut self, ip: Ipv4Addr) -> bool {
+        self.pool_mut(ip).is_some_and(|pool| pool.take_ip(ip))
+    }
+
+    pub fn confirm_lease(&mut self, ip: Ipv4Addr) -> bool {
+        self.pool_mut(ip).is_some_and(|pool| pool.confirm_lease(ip))
+    }
 
     pub fn release_ip(&mut self, ip: &Ipv4Addr) {
-        if let Some(pos) = self.leased_ips.iter().position(|x| x == ip) {
-            self.leased_ips.remove(pos);
-            self.available_ips.push(*ip);
+        if let Some(pool) = self.pool_mut(*ip) {
+            pool.release_ip(ip);
         }
     }
+
+    pub fn exclude(&mut self, ip: Ipv4Addr) {
+        if let Some(pool) = self.pool_mut(ip) {
+            pool.exclude(ip);
+        }
+    }
+
+    /// Exclut la plage de chaque pool qu'elle recouvre.
+    pub fn exclude_range(&mut self, start: Ipv4Addr, end: Ipv4Addr) {
+        for pool in &mut self.pools {
+            pool.exclude_range(start, end);
+        }
+    }
+
+    pub fn include(&mut self, ip: Ipv4Addr) {
+        if let Some(pool) = self.pool_mut(ip) {
+            pool.include(ip);
+        }
+    }
+
+    fn pool(&self, ip: Ipv4Addr) -> Option<&IpPool> {
+        self.pools.iter().find(|pool| pool.subnet().contains(ip))
+    }
+
+    fn pool_mut(&mut self, ip: Ipv4Addr) -> Option<&mut IpPool> {
+        self.pools.iter_mut().find(|pool| pool.subnet().contains(ip))
+    }
 }
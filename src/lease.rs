@@ -0,0 +1,220 @@
+use std::fmt;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::message::CLIENT_PORT;
+use crate::relay_agent::RelayAgentInfo;
+
+/// Statut en base d'une offre jamais confirmée, distinct d'un bail expiré.
+pub const OFFER_EXPIRED: &str = "OFFER_EXPIRED";
+/// Statut en base d'un bail libéré depuis le menu d'administration.
+pub const RELEASED_BY_ADMIN: &str = "RELEASED_BY_ADMIN";
+/// Statut en base d'une offre retirée parce que le client a retenu un autre serveur.
+pub const OFFER_WITHDRAWN: &str = "OFFER_WITHDRAWN";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LeaseState {
+    Offered,
+    Bound,
+    Renewing,
+    Released,
+    Expired,
+    Declined,
+    Abandoned,
+}
+
+impl LeaseState {
+    /// Libellé stocké dans la colonne `status` de la table `leases`.
+    /// `Bound` garde le libellé historique "ACKNOWLEDGED".
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LeaseState::Offered => "OFFERED",
+            LeaseState::Bound => "ACKNOWLEDGED",
+            LeaseState::Renewing => "RENEWING",
+            LeaseState::Released => "RELEASED",
+            LeaseState::Expired => "EXPIRED",
+            LeaseState::Declined => "DECLINED",
+            LeaseState::Abandoned => "ABANDONED",
+        }
+    }
+
+    /// Un bail actif réserve encore son adresse.
+    pub fn is_active(self) -> bool {
+        matches!(self, LeaseState::Offered | LeaseState::Bound | LeaseState::Renewing)
+    }
+
+    pub fn can_transition_to(self, next: LeaseState) -> bool {
+        use LeaseState::*;
+        match self {
+            // Une offre peut être renvoyée (retransmission) avant d'être confirmée
+            Offered => matches!(next, Offered | Bound | Released | Expired | Declined | Abandoned),
+            // Un bail confirmé peut être re-confirmé (REQUEST répété)
+            Bound => matches!(next, Bound | Renewing | Released | Expired | Declined | Abandoned),
+            Renewing => matches!(next, Bound | Released | Expired | Declined | Abandoned),
+            Released | Expired | Declined | Abandoned => false,
+        }
+    }
+
+    /// États actifs, pour retrouver en base les lignes encore ouvertes.
+    pub fn active_states() -> [LeaseState; 3] {
+        [LeaseState::Offered, LeaseState::Bound, LeaseState::Renewing]
+    }
+}
+
+impl fmt::Display for LeaseState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for LeaseState {
+    type Err = String;
+
+    // Accepte aussi les libellés historiques écrits par les versions précédentes
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "OFFERED" => Ok(LeaseState::Offered),
+            "ACKNOWLEDGED" => Ok(LeaseState::Bound),
+            "RENEWING" => Ok(LeaseState::Renewing),
+            "RELEASED" | RELEASED_BY_ADMIN | OFFER_WITHDRAWN => Ok(LeaseState::Released),
+            "EXPIRED" | OFFER_EXPIRED => Ok(LeaseState::Expired),
+            "DECLINED" => Ok(LeaseState::Declined),
+            "ABANDONED" => Ok(LeaseState::Abandoned),
+            other => Err(format!("statut de bail inconnu : {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidTransition {
+    pub from: LeaseState,
+    pub to: LeaseState,
+}
+
+impl fmt::Display for InvalidTransition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "transition de bail invalide : {} -> {}", self.from, self.to)
+    }
+}
+
+impl std::error::Error for InvalidTransition {}
+
+/// Bail (ou offre) détenu par un client, identifié par son option 61 ou son adresse MAC.
+#[derive(Debug, Clone)]
+pub struct Lease {
+    pub client_id: String,
+    pub mac: String,
+    pub ip: Ipv4Addr,
+    pub state: LeaseState,
+    // Métadonnées de transport : dernière adresse source et dernier xid vus
+    pub addr: SocketAddr,
+    pub xid: u32,
+    // Option 82 du dernier message relayé (circuit, relais), à journaliser avec le bail
+    pub relay_agent: Option<RelayAgentInfo>,
+    // Échéance de l'offre tant que le client n'a pas envoyé de REQUEST
+    pub offer_deadline: Option<Instant>,
+    // Durée accordée (secondes) et fin du bail une fois confirmé
+    pub lease_time: u32,
+    pub end_time: Option<DateTime<Utc>>,
+}
+
+impl Lease {
+    /// Nouvelle offre réservée pendant `hold`.
+    pub fn offer(client_id: &str, mac: &str, ip: Ipv4Addr, addr: SocketAddr, xid: u32, hold: Duration) -> Self {
+        Lease {
+            client_id: client_id.to_string(),
+            mac: mac.to_string(),
+            ip,
+            state: LeaseState::Offered,
+            addr,
+            xid,
+            relay_agent: None,
+            offer_deadline: Some(Instant::now() + hold),
+            lease_time: 0,
+            end_time: None,
+        }
+    }
+
+    /// Bail confirmé rechargé depuis la base au démarrage du serveur. La durée
+    /// accordée est ramenée au temps restant ; le client est joint sur son adresse louée.
+    pub fn restore(client_id: &str, mac: &str, ip: Ipv4Addr, end_time: DateTime<Utc>) -> Self {
+        let remaining = (end_time - Utc::now()).num_seconds().clamp(0, u32::MAX as i64);
+        Lease {
+            client_id: client_id.to_string(),
+            mac: mac.to_string(),
+            ip,
+            state: LeaseState::Bound,
+            addr: SocketAddr::new(ip.into(), CLIENT_PORT),
+            xid: 0,
+            relay_agent: None,
+            offer_deadline: None,
+            lease_time: remaining as u32,
+            end_time: Some(end_time),
+        }
+    }
+
+    /// Applique une transition et renvoie le statut à enregistrer en base.
+    pub fn transition(&mut self, next: LeaseState) -> Result<&'static str, InvalidTransition> {
+        if !self.state.can_transition_to(next) {
+            return Err(InvalidTransition { from: self.state, to: next });
+        }
+        let status = match (self.state, next) {
+            (LeaseState::Offered, LeaseState::Expired) => OFFER_EXPIRED,
+            _ => next.as_str(),
+        };
+        self.state = next;
+        Ok(status)
+    }
+
+    /// Confirme le bail pour `lease_time` secondes à partir de maintenant.
+    pub fn bind(&mut self, lease_time: u32) -> Result<&'static str, InvalidTransition> {
+        let status = self.transition(LeaseState::Bound)?;
+        self.offer_deadline = None;
+        self.lease_time = lease_time;
+        self.end_time = Some(Utc::now() + TimeDelta::seconds(lease_time as i64));
+        Ok(status)
+    }
+
+    /// Prolonge un bail confirmé (RENEWING ou REBINDING) pour `lease_time` secondes.
+    pub fn renew(&mut self, lease_time: u32) -> Result<&'static str, InvalidTransition> {
+        self.transition(LeaseState::Renewing)?;
+        self.bind(lease_time)
+    }
+
+    /// Vrai une fois passé T2 (7/8 du bail), où le client diffuse ses demandes
+    /// de prolongation à tous les serveurs.
+    pub fn rebinding_due(&self, now: DateTime<Utc>) -> bool {
+        self.end_time
+            .is_some_and(|end| end - TimeDelta::seconds(self.lease_time as i64 / 8) <= now)
+    }
+
+    /// Libération demandée depuis le menu d'administration.
+    pub fn release_by_admin(&mut self) -> Result<&'static str, InvalidTransition> {
+        self.transition(LeaseState::Released)?;
+        Ok(RELEASED_BY_ADMIN)
+    }
+
+    /// Retire une offre non confirmée : le client a choisi l'offre d'un autre
+    /// serveur (REQUEST portant un autre identifiant serveur, RFC 2131, 4.3.2).
+    pub fn withdraw_offer(&mut self) -> Result<&'static str, InvalidTransition> {
+        if self.state != LeaseState::Offered {
+            return Err(InvalidTransition { from: self.state, to: LeaseState::Released });
+        }
+        self.transition(LeaseState::Released)?;
+        Ok(OFFER_WITHDRAWN)
+    }
+
+    /// Offre non confirmée dont le délai de réservation est dépassé.
+    pub fn offer_expired(&self, now: Instant) -> bool {
+        self.state == LeaseState::Offered && self.offer_deadline.is_some_and(|deadline| deadline <= now)
+    }
+
+    /// Bail confirmé dont la date de fin est dépassée.
+    pub fn lease_expired(&self, now: DateTime<Utc>) -> bool {
+        matches!(self.state, LeaseState::Bound | LeaseState::Renewing)
+            && self.end_time.is_some_and(|end| end <= now)
+    }
+}
@@ -1 +1,13 @@
+pub mod config;
+pub mod db;
 pub mod ip_pool;
+pub mod lease;
+pub mod link;
+pub mod message;
+pub mod options;
+pub mod probe;
+pub mod relay_agent;
+pub mod reservation;
+pub mod server;
+pub mod store;
+pub mod utils;
@@ -0,0 +1,75 @@
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddrV4};
+use std::sync::Mutex;
+
+use pnet::datalink::{self, Channel, DataLinkSender, MacAddr};
+use pnet::packet::ethernet::{EtherTypes, MutableEthernetPacket};
+use pnet::packet::ip::IpNextHeaderProtocols;
+use pnet::packet::ipv4::{self, MutableIpv4Packet};
+use pnet::packet::udp::{self, MutableUdpPacket};
+use pnet::packet::Packet;
+
+use crate::message::SERVER_PORT;
+
+// Tailles des en-têtes Ethernet, IPv4 (sans options) et UDP
+const ETHERNET_LEN: usize = 14;
+const IPV4_LEN: usize = 20;
+const UDP_LEN: usize = 8;
+
+/// Envoi direct sur le lien (RFC 2131, 4.1) : trame Ethernet adressée à
+/// `chaddr`, datagramme IP vers `yiaddr`, pour un client qui n'a pas encore
+/// d'adresse et n'a pas demandé de diffusion. Évite de passer par la table ARP
+/// du noyau. Nécessite un socket brut (root ou CAP_NET_RAW).
+pub struct LinkSender {
+    tx: Mutex<Box<dyn DataLinkSender>>,
+    mac: MacAddr,
+    ip: Ipv4Addr,
+}
+
+impl LinkSender {
+    /// Ouvre l'interface qui porte `server_ip`.
+    pub fn new(server_ip: Ipv4Addr) -> io::Result<Self> {
+        let interface = datalink::interfaces()
+            .into_iter()
+            .find(|iface| iface.ips.iter().any(|net| net.ip() == IpAddr::V4(server_ip)))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("aucune interface ne porte {}", server_ip)))?;
+        let mac = interface.mac.unwrap_or(MacAddr::zero());
+        match datalink::channel(&interface, Default::default())? {
+            Channel::Ethernet(tx, _) => Ok(LinkSender { tx: Mutex::new(tx), mac, ip: server_ip }),
+            _ => Err(io::Error::new(io::ErrorKind::Unsupported, format!("{} n'est pas une interface Ethernet", interface.name))),
+        }
+    }
+
+    /// Envoie `payload` depuis le port serveur vers `dest`, à l'adresse matérielle `chaddr`.
+    pub fn send(&self, chaddr: [u8; 6], dest: SocketAddrV4, payload: &[u8]) -> io::Result<()> {
+        let mut frame = vec![0u8; ETHERNET_LEN + IPV4_LEN + UDP_LEN + payload.len()];
+
+        let mut datagram = MutableUdpPacket::new(&mut frame[ETHERNET_LEN + IPV4_LEN..]).expect("tampon UDP trop petit");
+        datagram.set_source(SERVER_PORT);
+        datagram.set_destination(dest.port());
+        datagram.set_length((UDP_LEN + payload.len()) as u16);
+        datagram.set_payload(payload);
+        let checksum = udp::ipv4_checksum(&datagram.to_immutable(), &self.ip, dest.ip());
+        datagram.set_checksum(checksum);
+
+        let mut packet = MutableIpv4Packet::new(&mut frame[ETHERNET_LEN..]).expect("tampon IP trop petit");
+        packet.set_version(4);
+        packet.set_header_length((IPV4_LEN / 4) as u8);
+        packet.set_total_length((IPV4_LEN + UDP_LEN + payload.len()) as u16);
+        packet.set_ttl(64);
+        packet.set_next_level_protocol(IpNextHeaderProtocols::Udp);
+        packet.set_source(self.ip);
+        packet.set_destination(*dest.ip());
+        let checksum = ipv4::checksum(&packet.to_immutable());
+        packet.set_checksum(checksum);
+
+        let mut ethernet = MutableEthernetPacket::new(&mut frame).expect("tampon Ethernet trop petit");
+        ethernet.set_destination(MacAddr::from(chaddr));
+        ethernet.set_source(self.mac);
+        ethernet.set_ethertype(EtherTypes::Ipv4);
+
+        let mut tx = self.tx.lock().unwrap();
+        tx.send_to(ethernet.packet(), None)
+            .unwrap_or_else(|| Err(io::Error::other("trame non envoyée")))
+    }
+}
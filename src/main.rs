@@ -1,11 +1,12 @@
-mod ip_pool;
-use ip_pool::IpPool;
+use dhcp_demo::ip_pool::IpPool;
 use std::net::Ipv4Addr;
 
 fn main() {
     println!("🚀 Test du module IpPool...");
 
-    let mut pool = IpPool::new(100, 102);
+    let subnet = "192.168.1.0/24".parse().unwrap();
+    let range = (Ipv4Addr::new(192, 168, 1, 100), Ipv4Addr::new(192, 168, 1, 102));
+    let mut pool = IpPool::new(subnet, &[range], &[]).unwrap();
 
     println!("🔍 Attribution de 3 IP :");
     for _ in 0..3 {
@@ -0,0 +1,427 @@
+use std::fmt;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+
+use crate::options::{self, decode_options, encode_options, DhcpOption};
+use crate::relay_agent::RelayAgentInfo;
+
+// Ports UDP standards (RFC 2131)
+pub const SERVER_PORT: u16 = 67;
+pub const CLIENT_PORT: u16 = 68;
+
+// Valeurs du champ `op`
+pub const BOOTREQUEST: u8 = 1;
+pub const BOOTREPLY: u8 = 2;
+
+pub const HTYPE_ETHERNET: u8 = 1;
+pub const FLAG_BROADCAST: u16 = 0x8000;
+pub const MAGIC_COOKIE: [u8; 4] = [99, 130, 83, 99];
+
+// Taille de l'en-tête BOOTP fixe (jusqu'au champ `file` inclus)
+const HEADER_LEN: usize = 236;
+// Taille minimale d'un message BOOTP, certains relais rejettent les paquets plus courts
+const MIN_PACKET_LEN: usize = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageType {
+    Discover,
+    Offer,
+    Request,
+    Decline,
+    Ack,
+    Nak,
+    Release,
+    Inform,
+    ForceRenew,
+}
+
+impl MessageType {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(MessageType::Discover),
+            2 => Some(MessageType::Offer),
+            3 => Some(MessageType::Request),
+            4 => Some(MessageType::Decline),
+            5 => Some(MessageType::Ack),
+            6 => Some(MessageType::Nak),
+            7 => Some(MessageType::Release),
+            8 => Some(MessageType::Inform),
+            9 => Some(MessageType::ForceRenew),
+            _ => None,
+        }
+    }
+
+    pub fn as_u8(self) -> u8 {
+        match self {
+            MessageType::Discover => 1,
+            MessageType::Offer => 2,
+            MessageType::Request => 3,
+            MessageType::Decline => 4,
+            MessageType::Ack => 5,
+            MessageType::Nak => 6,
+            MessageType::Release => 7,
+            MessageType::Inform => 8,
+            MessageType::ForceRenew => 9,
+        }
+    }
+}
+
+impl fmt::Display for MessageType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            MessageType::Discover => "DISCOVER",
+            MessageType::Offer => "OFFER",
+            MessageType::Request => "REQUEST",
+            MessageType::Decline => "DECLINE",
+            MessageType::Ack => "ACK",
+            MessageType::Nak => "NAK",
+            MessageType::Release => "RELEASE",
+            MessageType::Inform => "INFORM",
+            MessageType::ForceRenew => "FORCERENEW",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// État du client qui émet un DHCPREQUEST (RFC 2131, 4.3.2).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestState {
+    /// Réponse à une offre : identifiant serveur (option 54) présent.
+    Selecting,
+    /// Redémarrage : adresse demandée (option 50) sans identifiant serveur, `ciaddr` nul.
+    InitReboot,
+    /// Prolongation d'un bail : `ciaddr` renseigné. RENEWING (unicast, à T1) et
+    /// REBINDING (diffusion, à T2) ne diffèrent que par la destination IP.
+    Renewing,
+}
+
+impl fmt::Display for RequestState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            RequestState::Selecting => "SELECTING",
+            RequestState::InitReboot => "INIT-REBOOT",
+            RequestState::Renewing => "RENEWING",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Où envoyer une réponse serveur (voir `DhcpMessage::reply_destination`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplyDestination {
+    /// Datagramme UDP ordinaire : relais, `ciaddr`, source de la requête ou diffusion.
+    Ip(SocketAddr),
+    /// Client sans adresse : trame adressée à `chaddr`, datagramme vers `yiaddr`.
+    Link { chaddr: [u8; 6], addr: SocketAddrV4 },
+}
+
+impl fmt::Display for ReplyDestination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplyDestination::Ip(addr) => write!(f, "{}", addr),
+            ReplyDestination::Link { chaddr, addr } => {
+                let mac: Vec<String> = chaddr.iter().map(|b| format!("{:02X}", b)).collect();
+                write!(f, "{} ({})", addr, mac.join(":"))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MessageError {
+    TooShort(usize),
+    BadMagicCookie,
+    TruncatedOption(u8),
+}
+
+impl fmt::Display for MessageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MessageError::TooShort(len) => write!(f, "message trop court ({} octets)", len),
+            MessageError::BadMagicCookie => write!(f, "magic cookie DHCP absent"),
+            MessageError::TruncatedOption(code) => write!(f, "option {} tronquée", code),
+        }
+    }
+}
+
+impl std::error::Error for MessageError {}
+
+/// Message BOOTP/DHCP tel que transporté sur le réseau (RFC 2131, section 2).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DhcpMessage {
+    pub op: u8,
+    pub htype: u8,
+    pub hlen: u8,
+    pub hops: u8,
+    pub xid: u32,
+    pub secs: u16,
+    pub flags: u16,
+    pub ciaddr: Ipv4Addr,
+    pub yiaddr: Ipv4Addr,
+    pub siaddr: Ipv4Addr,
+    pub giaddr: Ipv4Addr,
+    pub chaddr: [u8; 16],
+    pub sname: [u8; 64],
+    pub file: [u8; 128],
+    /// Options décodées, sans les options PAD et END.
+    pub options: Vec<DhcpOption>,
+}
+
+impl DhcpMessage {
+    /// Construit un message vierge pour une adresse MAC Ethernet.
+    pub fn new(op: u8, msg_type: MessageType, xid: u32, mac: [u8; 6]) -> Self {
+        let mut chaddr = [0u8; 16];
+        chaddr[..6].copy_from_slice(&mac);
+        let mut msg = DhcpMessage {
+            op,
+            htype: HTYPE_ETHERNET,
+            hlen: 6,
+            hops: 0,
+            xid,
+            secs: 0,
+            flags: 0,
+            ciaddr: Ipv4Addr::UNSPECIFIED,
+            yiaddr: Ipv4Addr::UNSPECIFIED,
+            siaddr: Ipv4Addr::UNSPECIFIED,
+            giaddr: Ipv4Addr::UNSPECIFIED,
+            chaddr,
+            sname: [0u8; 64],
+            file: [0u8; 128],
+            options: Vec::new(),
+        };
+        msg.set_option(DhcpOption::MessageType(msg_type));
+        msg
+    }
+
+    /// Construit la réponse serveur (BOOTREPLY) à cette requête. L'option 82
+    /// d'une requête relayée est renvoyée telle quelle au relais (RFC 3046, 2.2).
+    pub fn reply(&self, msg_type: MessageType) -> Self {
+        let mut msg = DhcpMessage {
+            op: BOOTREPLY,
+            htype: self.htype,
+            hlen: self.hlen,
+            hops: 0,
+            xid: self.xid,
+            secs: 0,
+            flags: self.flags,
+            ciaddr: Ipv4Addr::UNSPECIFIED,
+            yiaddr: Ipv4Addr::UNSPECIFIED,
+            siaddr: Ipv4Addr::UNSPECIFIED,
+            giaddr: self.giaddr,
+            chaddr: self.chaddr,
+            sname: [0u8; 64],
+            file: [0u8; 128],
+            options: Vec::new(),
+        };
+        msg.set_option(DhcpOption::MessageType(msg_type));
+        if let Some(info) = self.relay_agent_info() {
+            msg.set_option(DhcpOption::RelayAgentInformation(info.clone()));
+        }
+        msg
+    }
+
+    pub fn parse(buf: &[u8]) -> Result<Self, MessageError> {
+        if buf.len() < HEADER_LEN + MAGIC_COOKIE.len() {
+            return Err(MessageError::TooShort(buf.len()));
+        }
+        if buf[HEADER_LEN..HEADER_LEN + 4] != MAGIC_COOKIE {
+            return Err(MessageError::BadMagicCookie);
+        }
+
+        let addr = |at: usize| Ipv4Addr::new(buf[at], buf[at + 1], buf[at + 2], buf[at + 3]);
+        let mut chaddr = [0u8; 16];
+        chaddr.copy_from_slice(&buf[28..44]);
+        let mut sname = [0u8; 64];
+        sname.copy_from_slice(&buf[44..108]);
+        let mut file = [0u8; 128];
+        file.copy_from_slice(&buf[108..236]);
+
+        Ok(DhcpMessage {
+            op: buf[0],
+            htype: buf[1],
+            hlen: buf[2],
+            hops: buf[3],
+            xid: u32::from_be_bytes([buf[4], buf[5], buf[6], buf[7]]),
+            secs: u16::from_be_bytes([buf[8], buf[9]]),
+            flags: u16::from_be_bytes([buf[10], buf[11]]),
+            ciaddr: addr(12),
+            yiaddr: addr(16),
+            siaddr: addr(20),
+            giaddr: addr(24),
+            chaddr,
+            sname,
+            file,
+            options: decode_options(&buf[HEADER_LEN + 4..])?,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(MIN_PACKET_LEN);
+        buf.push(self.op);
+        buf.push(self.htype);
+        buf.push(self.hlen);
+        buf.push(self.hops);
+        buf.extend_from_slice(&self.xid.to_be_bytes());
+        buf.extend_from_slice(&self.secs.to_be_bytes());
+        buf.extend_from_slice(&self.flags.to_be_bytes());
+        buf.extend_from_slice(&self.ciaddr.octets());
+        buf.extend_from_slice(&self.yiaddr.octets());
+        buf.extend_from_slice(&self.siaddr.octets());
+        buf.extend_from_slice(&self.giaddr.octets());
+        buf.extend_from_slice(&self.chaddr);
+        buf.extend_from_slice(&self.sname);
+        buf.extend_from_slice(&self.file);
+        buf.extend_from_slice(&MAGIC_COOKIE);
+
+        encode_options(&self.options, &mut buf);
+
+        if buf.len() < MIN_PACKET_LEN {
+            buf.resize(MIN_PACKET_LEN, options::PAD);
+        }
+        buf
+    }
+
+    pub fn option(&self, code: u8) -> Option<&DhcpOption> {
+        self.options.iter().find(|opt| opt.code() == code)
+    }
+
+    /// Ajoute une option ou remplace celle de même code.
+    pub fn set_option(&mut self, option: DhcpOption) {
+        match self.options.iter_mut().find(|opt| opt.code() == option.code()) {
+            Some(existing) => *existing = option,
+            None => self.options.push(option),
+        }
+    }
+
+    pub fn message_type(&self) -> Option<MessageType> {
+        match self.option(options::MESSAGE_TYPE) {
+            Some(DhcpOption::MessageType(msg_type)) => Some(*msg_type),
+            _ => None,
+        }
+    }
+
+    /// Adresse demandée par le client : option 50, sinon `ciaddr`.
+    pub fn requested_ip(&self) -> Option<Ipv4Addr> {
+        match self.option(options::REQUESTED_IP_ADDRESS) {
+            Some(DhcpOption::RequestedIpAddress(ip)) => Some(*ip),
+            _ if !self.ciaddr.is_unspecified() => Some(self.ciaddr),
+            _ => None,
+        }
+    }
+
+    /// Classe un REQUEST selon les champs renseignés et renvoie l'adresse visée :
+    /// `ciaddr` pour une prolongation, l'option 50 sinon. `None` si la combinaison
+    /// ne correspond à aucun état (ni option 50 ni `ciaddr`, ou SELECTING sans option 50).
+    pub fn request_state(&self) -> Option<(RequestState, Ipv4Addr)> {
+        let requested = match self.option(options::REQUESTED_IP_ADDRESS) {
+            Some(DhcpOption::RequestedIpAddress(ip)) => Some(*ip),
+            _ => None,
+        };
+        if self.server_identifier().is_some() {
+            requested.map(|ip| (RequestState::Selecting, ip))
+        } else if !self.ciaddr.is_unspecified() {
+            Some((RequestState::Renewing, self.ciaddr))
+        } else {
+            requested.map(|ip| (RequestState::InitReboot, ip))
+        }
+    }
+
+    pub fn lease_time(&self) -> Option<u32> {
+        match self.option(options::IP_ADDRESS_LEASE_TIME) {
+            Some(DhcpOption::IpAddressLeaseTime(secs)) => Some(*secs),
+            _ => None,
+        }
+    }
+
+    pub fn server_identifier(&self) -> Option<Ipv4Addr> {
+        match self.option(options::SERVER_IDENTIFIER) {
+            Some(DhcpOption::ServerIdentifier(ip)) => Some(*ip),
+            _ => None,
+        }
+    }
+
+    /// Option 82 ajoutée par un relais ; ignorée sur une requête non relayée,
+    /// où elle ne peut venir que du client lui-même.
+    pub fn relay_agent_info(&self) -> Option<&RelayAgentInfo> {
+        match self.option(options::RELAY_AGENT_INFORMATION) {
+            Some(DhcpOption::RelayAgentInformation(info)) if !self.giaddr.is_unspecified() => Some(info),
+            _ => None,
+        }
+    }
+
+    /// Adresse désignant le lien du client : la sous-option link-selection
+    /// (RFC 3527) si le relais l'a fournie, sinon `giaddr`.
+    pub fn link_address(&self) -> Ipv4Addr {
+        self.relay_agent_info()
+            .and_then(RelayAgentInfo::link_selection)
+            .unwrap_or(self.giaddr)
+    }
+
+    pub fn parameter_request_list(&self) -> Option<&[u8]> {
+        match self.option(options::PARAMETER_REQUEST_LIST) {
+            Some(DhcpOption::ParameterRequestList(codes)) => Some(codes),
+            _ => None,
+        }
+    }
+
+    /// Clé identifiant le client : option 61 si présente, sinon l'adresse matérielle.
+    /// Les deux sont rendues en hexadécimal majuscule, comme `mac_string`.
+    pub fn client_id(&self) -> String {
+        match self.option(options::CLIENT_IDENTIFIER) {
+            Some(DhcpOption::ClientIdentifier(id)) => {
+                id.iter().map(|b| format!("{:02X}", b)).collect()
+            }
+            _ => self.mac_string(),
+        }
+    }
+
+    pub fn is_broadcast(&self) -> bool {
+        self.flags & FLAG_BROADCAST != 0
+    }
+
+    /// Destination de cette réponse serveur (RFC 2131, 4.1), `src` étant la
+    /// source de la requête :
+    /// - requête relayée : le relais (`giaddr`), sur `relay_port` ;
+    /// - client hors du port 68 (outils de test, client de démonstration) : `src` ;
+    /// - DHCPNAK : diffusion, le client n'a pas d'adresse utilisable ;
+    /// - `ciaddr` renseigné (RENEWING, INFORM) : unicast vers `ciaddr` ;
+    /// - bit broadcast levé : diffusion ;
+    /// - sinon unicast vers `yiaddr` au niveau liaison, à l'adresse `chaddr`
+    ///   (diffusion si `chaddr` n'est pas une adresse Ethernet).
+    pub fn reply_destination(&self, src: SocketAddr, relay_port: u16) -> ReplyDestination {
+        let broadcast = ReplyDestination::Ip(SocketAddr::new(Ipv4Addr::BROADCAST.into(), CLIENT_PORT));
+        if !self.giaddr.is_unspecified() {
+            ReplyDestination::Ip(SocketAddr::new(self.giaddr.into(), relay_port))
+        } else if src.port() != CLIENT_PORT && !src.ip().is_unspecified() {
+            ReplyDestination::Ip(src)
+        } else if self.message_type() == Some(MessageType::Nak) {
+            broadcast
+        } else if !self.ciaddr.is_unspecified() {
+            ReplyDestination::Ip(SocketAddr::new(self.ciaddr.into(), CLIENT_PORT))
+        } else if self.is_broadcast() || self.yiaddr.is_unspecified() || self.htype != HTYPE_ETHERNET || self.hlen != 6 {
+            broadcast
+        } else {
+            let mut chaddr = [0u8; 6];
+            chaddr.copy_from_slice(&self.chaddr[..6]);
+            ReplyDestination::Link { chaddr, addr: SocketAddrV4::new(self.yiaddr, CLIENT_PORT) }
+        }
+    }
+
+    /// Adresse matérielle du client, au format utilisé par la base (ex: "C858C0610CFB").
+    pub fn mac_string(&self) -> String {
+        let len = (self.hlen as usize).min(self.chaddr.len());
+        self.chaddr[..len].iter().map(|b| format!("{:02X}", b)).collect()
+    }
+}
+
+/// Convertit une adresse MAC textuelle ("C858C0610CFB", "c8:58:c0:61:0c:fb"...) en octets.
+pub fn parse_mac(mac: &str) -> Option<[u8; 6]> {
+    let hex: String = mac.chars().filter(|c| c.is_ascii_hexdigit()).collect();
+    if hex.len() != 12 {
+        return None;
+    }
+    let mut bytes = [0u8; 6];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(bytes)
+}
@@ -0,0 +1,253 @@
+use std::net::Ipv4Addr;
+
+use crate::message::{MessageError, MessageType};
+use crate::relay_agent::RelayAgentInfo;
+
+pub const PAD: u8 = 0;
+pub const SUBNET_MASK: u8 = 1;
+pub const ROUTER: u8 = 3;
+pub const DOMAIN_NAME_SERVER: u8 = 6;
+pub const HOST_NAME: u8 = 12;
+pub const DOMAIN_NAME: u8 = 15;
+pub const REQUESTED_IP_ADDRESS: u8 = 50;
+pub const IP_ADDRESS_LEASE_TIME: u8 = 51;
+pub const MESSAGE_TYPE: u8 = 53;
+pub const SERVER_IDENTIFIER: u8 = 54;
+pub const PARAMETER_REQUEST_LIST: u8 = 55;
+pub const MESSAGE: u8 = 56;
+pub const RENEWAL_TIME: u8 = 58;
+pub const REBINDING_TIME: u8 = 59;
+pub const CLIENT_IDENTIFIER: u8 = 61;
+pub const RELAY_AGENT_INFORMATION: u8 = 82;
+pub const END: u8 = 255;
+
+/// Option DHCP typée (RFC 2132). Les options inconnues ou mal formées sont
+/// conservées telles quelles dans `Unknown` pour être réémises sans perte.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DhcpOption {
+    Pad,
+    SubnetMask(Ipv4Addr),
+    Router(Vec<Ipv4Addr>),
+    DomainNameServer(Vec<Ipv4Addr>),
+    HostName(String),
+    DomainName(String),
+    RequestedIpAddress(Ipv4Addr),
+    IpAddressLeaseTime(u32),
+    MessageType(MessageType),
+    ServerIdentifier(Ipv4Addr),
+    ParameterRequestList(Vec<u8>),
+    Message(String),
+    RenewalTime(u32),
+    RebindingTime(u32),
+    ClientIdentifier(Vec<u8>),
+    RelayAgentInformation(RelayAgentInfo),
+    End,
+    Unknown(u8, Vec<u8>),
+}
+
+impl DhcpOption {
+    pub fn code(&self) -> u8 {
+        match self {
+            DhcpOption::Pad => PAD,
+            DhcpOption::SubnetMask(_) => SUBNET_MASK,
+            DhcpOption::Router(_) => ROUTER,
+            DhcpOption::DomainNameServer(_) => DOMAIN_NAME_SERVER,
+            DhcpOption::HostName(_) => HOST_NAME,
+            DhcpOption::DomainName(_) => DOMAIN_NAME,
+            DhcpOption::RequestedIpAddress(_) => REQUESTED_IP_ADDRESS,
+            DhcpOption::IpAddressLeaseTime(_) => IP_ADDRESS_LEASE_TIME,
+            DhcpOption::MessageType(_) => MESSAGE_TYPE,
+            DhcpOption::ServerIdentifier(_) => SERVER_IDENTIFIER,
+            DhcpOption::ParameterRequestList(_) => PARAMETER_REQUEST_LIST,
+            DhcpOption::Message(_) => MESSAGE,
+            DhcpOption::RenewalTime(_) => RENEWAL_TIME,
+            DhcpOption::RebindingTime(_) => REBINDING_TIME,
+            DhcpOption::ClientIdentifier(_) => CLIENT_IDENTIFIER,
+            DhcpOption::RelayAgentInformation(_) => RELAY_AGENT_INFORMATION,
+            DhcpOption::End => END,
+            DhcpOption::Unknown(code, _) => *code,
+        }
+    }
+
+    /// Interprète la valeur brute d'une option. Une longueur invalide pour une
+    /// option connue donne `Unknown` plutôt qu'une erreur.
+    pub fn from_raw(code: u8, data: &[u8]) -> Self {
+        let option = match code {
+            SUBNET_MASK => read_addr(data).map(DhcpOption::SubnetMask),
+            ROUTER => read_addr_list(data).map(DhcpOption::Router),
+            DOMAIN_NAME_SERVER => read_addr_list(data).map(DhcpOption::DomainNameServer),
+            HOST_NAME => String::from_utf8(data.to_vec()).ok().map(DhcpOption::HostName),
+            DOMAIN_NAME => String::from_utf8(data.to_vec()).ok().map(DhcpOption::DomainName),
+            REQUESTED_IP_ADDRESS => read_addr(data).map(DhcpOption::RequestedIpAddress),
+            IP_ADDRESS_LEASE_TIME => read_u32(data).map(DhcpOption::IpAddressLeaseTime),
+            MESSAGE_TYPE => match data {
+                [value] => MessageType::from_u8(*value).map(DhcpOption::MessageType),
+                _ => None,
+            },
+            SERVER_IDENTIFIER => read_addr(data).map(DhcpOption::ServerIdentifier),
+            PARAMETER_REQUEST_LIST => Some(DhcpOption::ParameterRequestList(data.to_vec())),
+            MESSAGE => String::from_utf8(data.to_vec()).ok().map(DhcpOption::Message),
+            RENEWAL_TIME => read_u32(data).map(DhcpOption::RenewalTime),
+            REBINDING_TIME => read_u32(data).map(DhcpOption::RebindingTime),
+            // RFC 2132 9.14 : au moins deux octets (type + identifiant)
+            CLIENT_IDENTIFIER if data.len() >= 2 => Some(DhcpOption::ClientIdentifier(data.to_vec())),
+            RELAY_AGENT_INFORMATION => RelayAgentInfo::parse(data).map(DhcpOption::RelayAgentInformation),
+            _ => None,
+        };
+        option.unwrap_or_else(|| DhcpOption::Unknown(code, data.to_vec()))
+    }
+
+    /// Valeur brute de l'option, sans le code ni la longueur.
+    pub fn to_raw(&self) -> Vec<u8> {
+        match self {
+            DhcpOption::Pad | DhcpOption::End => Vec::new(),
+            DhcpOption::SubnetMask(ip)
+            | DhcpOption::RequestedIpAddress(ip)
+            | DhcpOption::ServerIdentifier(ip) => ip.octets().to_vec(),
+            DhcpOption::Router(ips) | DhcpOption::DomainNameServer(ips) => {
+                ips.iter().flat_map(|ip| ip.octets()).collect()
+            }
+            DhcpOption::HostName(text) | DhcpOption::DomainName(text) | DhcpOption::Message(text) => {
+                text.as_bytes().to_vec()
+            }
+            DhcpOption::IpAddressLeaseTime(secs)
+            | DhcpOption::RenewalTime(secs)
+            | DhcpOption::RebindingTime(secs) => secs.to_be_bytes().to_vec(),
+            DhcpOption::MessageType(msg_type) => vec![msg_type.as_u8()],
+            DhcpOption::ParameterRequestList(codes) | DhcpOption::ClientIdentifier(codes) => codes.clone(),
+            DhcpOption::RelayAgentInformation(info) => info.to_raw(),
+            DhcpOption::Unknown(_, data) => data.clone(),
+        }
+    }
+}
+
+fn read_addr(data: &[u8]) -> Option<Ipv4Addr> {
+    match data {
+        &[a, b, c, d] => Some(Ipv4Addr::new(a, b, c, d)),
+        _ => None,
+    }
+}
+
+fn read_addr_list(data: &[u8]) -> Option<Vec<Ipv4Addr>> {
+    if data.is_empty() || !data.len().is_multiple_of(4) {
+        return None;
+    }
+    Some(data.chunks(4).filter_map(read_addr).collect())
+}
+
+fn read_u32(data: &[u8]) -> Option<u32> {
+    match data {
+        &[a, b, c, d] => Some(u32::from_be_bytes([a, b, c, d])),
+        _ => None,
+    }
+}
+
+// Options gérées par le serveur lui-même, qu'une réservation ne peut pas fixer
+const MANAGED: [u8; 10] = [
+    REQUESTED_IP_ADDRESS,
+    IP_ADDRESS_LEASE_TIME,
+    MESSAGE_TYPE,
+    SERVER_IDENTIFIER,
+    PARAMETER_REQUEST_LIST,
+    MESSAGE,
+    RENEWAL_TIME,
+    REBINDING_TIME,
+    CLIENT_IDENTIFIER,
+    RELAY_AGENT_INFORMATION,
+];
+
+/// Lit une liste d'options saisie par l'administrateur : `code=valeur` séparés
+/// par des points-virgules (ex: "3=10.0.0.1;15=lab.local;43=0x0104c0a80001").
+/// La valeur est une liste d'adresses IPv4 séparées par des virgules, des
+/// octets en hexadécimal préfixés par `0x` ou, à défaut, du texte.
+/// Renvoie l'entrée illisible en cas d'erreur.
+pub fn parse_option_list(list: &str) -> Result<Vec<DhcpOption>, String> {
+    list.split(';')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| parse_option(entry).ok_or_else(|| entry.to_string()))
+        .collect()
+}
+
+fn parse_option(entry: &str) -> Option<DhcpOption> {
+    let (code, value) = entry.split_once('=')?;
+    let code: u8 = code.trim().parse().ok()?;
+    let value = value.trim();
+    if code == PAD || code == END || MANAGED.contains(&code) || value.is_empty() {
+        return None;
+    }
+    let addrs: Option<Vec<Ipv4Addr>> = value.split(',').map(|ip| ip.trim().parse().ok()).collect();
+    let data: Vec<u8> = match (addrs, value.strip_prefix("0x")) {
+        (Some(addrs), _) => addrs.iter().flat_map(|ip| ip.octets()).collect(),
+        (None, Some(hex)) => {
+            if hex.is_empty() || !hex.len().is_multiple_of(2) || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return None;
+            }
+            (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok()).collect::<Option<_>>()?
+        }
+        (None, None) => value.as_bytes().to_vec(),
+    };
+    Some(DhcpOption::from_raw(code, &data))
+}
+
+/// Décode la zone d'options (après le magic cookie) jusqu'à l'option END.
+/// Les options PAD sont ignorées et les occurrences multiples d'un même code
+/// sont concaténées avant interprétation (RFC 3396).
+pub fn decode_options(buf: &[u8]) -> Result<Vec<DhcpOption>, MessageError> {
+    let mut raw: Vec<(u8, Vec<u8>)> = Vec::new();
+    let mut i = 0;
+    while i < buf.len() {
+        let code = buf[i];
+        match code {
+            PAD => {
+                i += 1;
+                continue;
+            }
+            END => break,
+            _ => {}
+        }
+        if i + 1 >= buf.len() {
+            return Err(MessageError::TruncatedOption(code));
+        }
+        let len = buf[i + 1] as usize;
+        let start = i + 2;
+        if start + len > buf.len() {
+            return Err(MessageError::TruncatedOption(code));
+        }
+        let data = &buf[start..start + len];
+        match raw.iter_mut().find(|(c, _)| *c == code) {
+            Some(existing) => existing.1.extend_from_slice(data),
+            None => raw.push((code, data.to_vec())),
+        }
+        i = start + len;
+    }
+    Ok(raw
+        .iter()
+        .map(|(code, data)| DhcpOption::from_raw(*code, data))
+        .collect())
+}
+
+/// Encode les options au format TLV et termine par l'option END.
+pub fn encode_options(options: &[DhcpOption], buf: &mut Vec<u8>) {
+    for option in options {
+        match option {
+            DhcpOption::Pad => buf.push(PAD),
+            DhcpOption::End => {}
+            _ => {
+                let code = option.code();
+                let data = option.to_raw();
+                // Une option de plus de 255 octets est découpée en plusieurs (RFC 3396)
+                for chunk in data.chunks(255) {
+                    buf.push(code);
+                    buf.push(chunk.len() as u8);
+                    buf.extend_from_slice(chunk);
+                }
+                if data.is_empty() {
+                    buf.push(code);
+                    buf.push(0);
+                }
+            }
+        }
+    }
+    buf.push(END);
+}
@@ -0,0 +1,87 @@
+use std::io;
+use std::net::{IpAddr, Ipv4Addr};
+use std::process;
+use std::time::{Duration, Instant};
+
+use pnet::packet::icmp::echo_reply::EchoReplyPacket;
+use pnet::packet::icmp::echo_request::MutableEchoRequestPacket;
+use pnet::packet::icmp::{self, IcmpPacket, IcmpTypes};
+use pnet::packet::ip::IpNextHeaderProtocols;
+use pnet::packet::Packet;
+use pnet::transport::{
+    icmp_packet_iter, transport_channel, TransportChannelType::Layer4, TransportProtocol::Ipv4,
+    TransportReceiver, TransportSender,
+};
+
+// En-tête ICMP (8 octets) + charge utile
+const ECHO_LEN: usize = 16;
+
+/// Détection de conflit avant OFFER (RFC 2131, 2.2) : un ping ICMP vers
+/// l'adresse candidate ; une réponse signifie qu'un hôte l'utilise déjà.
+/// Nécessite un socket brut (root ou CAP_NET_RAW).
+pub struct ConflictProbe {
+    tx: TransportSender,
+    rx: TransportReceiver,
+    timeout: Duration,
+    identifier: u16,
+    sequence: u16,
+}
+
+impl ConflictProbe {
+    pub fn new(timeout: Duration) -> io::Result<Self> {
+        let (tx, rx) = transport_channel(1500, Layer4(Ipv4(IpNextHeaderProtocols::Icmp)))?;
+        Ok(ConflictProbe {
+            tx,
+            rx,
+            timeout,
+            identifier: process::id() as u16,
+            sequence: 0,
+        })
+    }
+
+    /// Pingue toutes les adresses d'un coup et renvoie celles qui ont répondu
+    /// avant l'expiration du délai : un lot coûte un seul délai, pas un par adresse.
+    pub fn responding(&mut self, ips: &[Ipv4Addr]) -> io::Result<Vec<Ipv4Addr>> {
+        // Numéros de séquence attendus, dans l'ordre de `ips`
+        let mut pending = Vec::with_capacity(ips.len());
+        for &ip in ips {
+            self.sequence = self.sequence.wrapping_add(1);
+            let mut buf = [0u8; ECHO_LEN];
+            let mut request = MutableEchoRequestPacket::new(&mut buf).expect("tampon ICMP trop petit");
+            request.set_icmp_type(IcmpTypes::EchoRequest);
+            request.set_identifier(self.identifier);
+            request.set_sequence_number(self.sequence);
+            let checksum = icmp::checksum(&IcmpPacket::new(request.packet()).expect("tampon ICMP trop petit"));
+            request.set_checksum(checksum);
+            self.tx.send_to(request, IpAddr::V4(ip))?;
+            pending.push((ip, self.sequence));
+        }
+
+        // Le socket brut reçoit tout le trafic ICMP : on ne garde que nos réponses
+        let deadline = Instant::now() + self.timeout;
+        let mut answered = Vec::new();
+        let mut replies = icmp_packet_iter(&mut self.rx);
+        while !pending.is_empty() {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+            let (packet, from) = match replies.next_with_timeout(remaining)? {
+                Some(reply) => reply,
+                None => break,
+            };
+            if packet.get_icmp_type() != IcmpTypes::EchoReply {
+                continue;
+            }
+            let reply = match EchoReplyPacket::new(packet.packet()) {
+                Some(reply) if reply.get_identifier() == self.identifier => reply,
+                _ => continue,
+            };
+            let sequence = reply.get_sequence_number();
+            if let Some(pos) = pending.iter().position(|&(ip, seq)| IpAddr::V4(ip) == from && seq == sequence) {
+                answered.push(pending.swap_remove(pos).0);
+            }
+        }
+        Ok(answered)
+    }
+}
@@ -0,0 +1,95 @@
+use std::fmt;
+use std::net::Ipv4Addr;
+
+// Sous-options de l'option 82 (RFC 3046, 3.1 et 3.2 ; RFC 3527 pour link-selection)
+pub const CIRCUIT_ID: u8 = 1;
+pub const REMOTE_ID: u8 = 2;
+pub const LINK_SELECTION: u8 = 5;
+
+/// Informations ajoutées par le relais (option 82, RFC 3046). Les sous-options
+/// sont conservées dans leur ordre d'origine pour être renvoyées à l'identique.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayAgentInfo {
+    suboptions: Vec<(u8, Vec<u8>)>,
+}
+
+impl RelayAgentInfo {
+    pub fn new(suboptions: Vec<(u8, Vec<u8>)>) -> Self {
+        RelayAgentInfo { suboptions }
+    }
+
+    /// Décode les sous-options TLV ; `None` si l'une d'elles est tronquée.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let mut suboptions = Vec::new();
+        let mut rest = data;
+        while let [code, len, tail @ ..] = rest {
+            let len = *len as usize;
+            if tail.len() < len {
+                return None;
+            }
+            suboptions.push((*code, tail[..len].to_vec()));
+            rest = &tail[len..];
+        }
+        rest.is_empty().then_some(RelayAgentInfo { suboptions })
+    }
+
+    pub fn to_raw(&self) -> Vec<u8> {
+        let mut raw = Vec::new();
+        for (code, data) in &self.suboptions {
+            raw.push(*code);
+            raw.push(data.len() as u8);
+            raw.extend_from_slice(data);
+        }
+        raw
+    }
+
+    pub fn suboption(&self, code: u8) -> Option<&[u8]> {
+        self.suboptions.iter().find(|(c, _)| *c == code).map(|(_, data)| data.as_slice())
+    }
+
+    /// Port ou circuit d'arrivée côté relais (ex: "Gi0/12"), sous forme lisible.
+    pub fn circuit_id(&self) -> Option<String> {
+        self.suboption(CIRCUIT_ID).map(format_id)
+    }
+
+    /// Identifiant du relais lui-même (ex: nom ou MAC du commutateur), sous forme lisible.
+    pub fn remote_id(&self) -> Option<String> {
+        self.suboption(REMOTE_ID).map(format_id)
+    }
+
+    /// Sous-réseau du client désigné explicitement par le relais (RFC 3527).
+    pub fn link_selection(&self) -> Option<Ipv4Addr> {
+        match self.suboption(LINK_SELECTION)? {
+            &[a, b, c, d] => Some(Ipv4Addr::new(a, b, c, d)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RelayAgentInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(circuit) = self.circuit_id() {
+            parts.push(format!("circuit {}", circuit));
+        }
+        if let Some(remote) = self.remote_id() {
+            parts.push(format!("relais {}", remote));
+        }
+        if let Some(link) = self.link_selection() {
+            parts.push(format!("lien {}", link));
+        }
+        if parts.is_empty() {
+            parts.push(format!("{} sous-option(s)", self.suboptions.len()));
+        }
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+/// Texte ASCII imprimable tel quel, sinon hexadécimal majuscule (comme les MAC).
+pub fn format_id(bytes: &[u8]) -> String {
+    if !bytes.is_empty() && bytes.iter().all(|b| b.is_ascii_graphic() || *b == b' ') {
+        String::from_utf8_lossy(bytes).into_owned()
+    } else {
+        bytes.iter().map(|b| format!("{:02X}", b)).collect()
+    }
+}
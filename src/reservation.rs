@@ -0,0 +1,58 @@
+use std::net::Ipv4Addr;
+
+use crate::options::DhcpOption;
+use crate::relay_agent::RelayAgentInfo;
+
+/// Réservation statique : le client désigné par son adresse MAC, son
+/// identifiant (option 61) ou le port du relais auquel il est branché
+/// (option 82) reçoit toujours la même adresse, hors du pool dynamique.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reservation {
+    pub mac: Option<String>,
+    pub client_id: Option<String>,
+    // Circuit-id (et éventuellement remote-id) de l'option 82 : réservation par port de commutateur
+    pub circuit_id: Option<String>,
+    pub remote_id: Option<String>,
+    pub ip: Ipv4Addr,
+    pub hostname: Option<String>,
+    // Options propres à l'hôte, prioritaires sur celles du serveur
+    pub options: Vec<DhcpOption>,
+}
+
+impl Reservation {
+    pub fn matches(&self, client_id: &str, mac: &str) -> bool {
+        self.client_id.as_deref() == Some(client_id) || self.mac.as_deref() == Some(mac)
+    }
+
+    /// Le message arrive par le circuit réservé ; le remote-id, s'il est
+    /// renseigné, distingue les ports homonymes de relais différents.
+    pub fn matches_relay(&self, info: &RelayAgentInfo) -> bool {
+        self.circuit_id.is_some()
+            && self.circuit_id == info.circuit_id()
+            && (self.remote_id.is_none() || self.remote_id == info.remote_id())
+    }
+
+    /// Désignation du client réservataire, pour l'affichage.
+    pub fn owner(&self) -> &str {
+        self.client_id
+            .as_deref()
+            .or(self.mac.as_deref())
+            .or(self.circuit_id.as_deref())
+            .unwrap_or("?")
+    }
+
+    /// Remplace ou complète `opts` par le nom d'hôte et les options de la
+    /// réservation, limités à ceux demandés via l'option 55 quand elle est présente.
+    pub fn apply_options(&self, opts: &mut Vec<DhcpOption>, requested: Option<&[u8]>) {
+        let host_name = self.hostname.clone().map(DhcpOption::HostName);
+        for option in host_name.iter().chain(&self.options) {
+            if requested.is_some_and(|codes| !codes.contains(&option.code())) {
+                continue;
+            }
+            match opts.iter_mut().find(|o| o.code() == option.code()) {
+                Some(existing) => *existing = option.clone(),
+                None => opts.push(option.clone()),
+            }
+        }
+    }
+}
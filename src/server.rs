@@ -0,0 +1,950 @@
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+
+use crate::config::ServerConfig;
+use crate::ip_pool::{parse_range, IpPool, PoolSet, Subnet};
+use crate::lease::{Lease, LeaseState};
+use crate::link::LinkSender;
+use crate::message::{
+    parse_mac, DhcpMessage, MessageType, ReplyDestination, RequestState, BOOTREPLY, BOOTREQUEST, CLIENT_PORT,
+    FLAG_BROADCAST,
+};
+use crate::options::{parse_option_list, DhcpOption};
+use crate::probe::ConflictProbe;
+use crate::reservation::Reservation;
+use crate::store::LeaseStore;
+use crate::utils::lookup_oui;
+
+// Nombre maximal d'adresses en conflit écartées pour un même DISCOVER
+const MAX_PROBES: usize = 8;
+
+// DISCOVER en attente du résultat du ping de son adresse candidate
+struct PendingOffer {
+    msg: DhcpMessage,
+    src: SocketAddr,
+    subnet: Subnet,
+    previous_ip: Option<Ipv4Addr>,
+    // Adresses déjà écartées pour ce DISCOVER
+    conflicts: usize,
+}
+
+/// Machine à états du serveur DHCP (RFC 2131) : baux, pool, réservations et
+/// exclusions. Les changements sont confiés à `store` pour être conservés.
+pub struct DhcpServer<S: LeaseStore> {
+    // Clé : identifiant client (option 61) ou, à défaut, adresse MAC
+    pub leases: HashMap<String, Lease>,
+    pub pool: PoolSet,
+    pub reservations: Vec<Reservation>,
+    // Exclusions ajoutées depuis le menu (en plus de celles de la configuration)
+    pub exclusions: Vec<(Ipv4Addr, Ipv4Addr)>,
+    // Dernière adresse détenue, par identifiant client et par MAC (allocation « collante »)
+    pub previous_ips: HashMap<String, Ipv4Addr>,
+    // Adresses en conflit (abandonnées ou refusées par DECLINE) et échéance de leur retour dans le pool
+    pub quarantine: HashMap<Ipv4Addr, Instant>,
+    // File du thread de ping (voir `spawn_prober`) ; sans lui, pas de détection de conflit
+    probe_tx: Option<Sender<Ipv4Addr>>,
+    // Adresses candidates en cours de ping : elles restent prises dans le pool d'ici la réponse
+    probing: HashMap<Ipv4Addr, PendingOffer>,
+    // Unicast vers les clients sans adresse ; à défaut, leurs réponses sont diffusées
+    link: Option<LinkSender>,
+    pub socket: UdpSocket,
+    pub config: ServerConfig,
+    pub store: S,
+}
+
+impl<S: LeaseStore> DhcpServer<S> {
+    pub fn new(socket: UdpSocket, pool: PoolSet, config: ServerConfig, store: S) -> Self {
+        let link = LinkSender::new(config.server_ip)
+            .map_err(|e| println!("⚠️ Unicast vers chaddr indisponible, réponses diffusées : {}", e))
+            .ok();
+
+        DhcpServer {
+            leases: HashMap::new(),
+            pool,
+            reservations: Vec::new(),
+            exclusions: Vec::new(),
+            previous_ips: HashMap::new(),
+            quarantine: HashMap::new(),
+            probe_tx: None,
+            probing: HashMap::new(),
+            link,
+            socket,
+            config,
+            store,
+        }
+    }
+
+    /// Reprend des baux rechargés au démarrage : leurs adresses sortent du pool.
+    pub fn restore_leases(&mut self, leases: Vec<Lease>) {
+        for lease in leases {
+            // Deux clients sur la même adresse : seul le premier rechargé la garde
+            if self.leases.values().any(|l| l.ip == lease.ip) {
+                continue;
+            }
+            self.pool.confirm_lease(lease.ip);
+            println!("♻️ Bail restauré : {} => {} (MAC {}, fin {})",
+                lease.client_id, lease.ip, lease.mac,
+                lease.end_time.map(|end| end.format("%Y-%m-%d %H:%M:%S UTC").to_string()).unwrap_or_default());
+            self.leases.insert(lease.client_id.clone(), lease);
+        }
+    }
+
+    /// Reprend des réservations rechargées : leurs adresses sortent du pool dynamique.
+    pub fn restore_reservations(&mut self, reservations: Vec<Reservation>) {
+        for reservation in &reservations {
+            self.pool.exclude(reservation.ip);
+        }
+        self.reservations = reservations;
+    }
+
+    pub fn restore_exclusions(&mut self, exclusions: Vec<(Ipv4Addr, Ipv4Addr)>) {
+        for &(start, end) in &exclusions {
+            self.pool.exclude_range(start, end);
+        }
+        self.exclusions = exclusions;
+    }
+
+    /// Reprend les adresses encore en quarantaine au démarrage : elles restent hors
+    /// du pool jusqu'à la fin du délai restant.
+    pub fn restore_quarantine(&mut self, quarantine: Vec<(Ipv4Addr, u64)>) {
+        for (ip, secs) in quarantine {
+            if self.leases.values().any(|l| l.ip == ip) {
+                continue;
+            }
+            self.pool.take_ip(ip);
+            self.quarantine_ip(ip, secs);
+            println!("⛔ {} toujours en quarantaine pour {} s", ip, secs);
+        }
+    }
+
+    // Rend une adresse au pool dynamique si plus rien ne l'en écarte ; un bail en cours la garde
+    fn return_to_pool(&mut self, ip: Ipv4Addr) {
+        let reserved = self.reservations.iter().any(|r| r.ip == ip);
+        let excluded = self.config.is_excluded(ip)
+            || self.exclusions.iter().any(|&(start, end)| start <= ip && ip <= end);
+        if reserved || excluded {
+            return;
+        }
+        self.pool.include(ip);
+        if self.leases.values().any(|lease| lease.ip == ip) {
+            self.pool.confirm_lease(ip);
+        }
+    }
+
+    // Réservation du client (identifiant ou MAC), à défaut celle du port de relais
+    // par lequel arrive sa requête (option 82)
+    fn reservation_for(&self, msg: &DhcpMessage) -> Option<&Reservation> {
+        let (client_id, mac) = (msg.client_id(), msg.mac_string());
+        self.reservations.iter().find(|r| r.matches(&client_id, &mac)).or_else(|| {
+            let info = msg.relay_agent_info()?;
+            self.reservations.iter().find(|r| r.matches_relay(info))
+        })
+    }
+
+    // Options d'un OFFER ou d'un ACK, complétées par celles de la réservation du client
+    fn reply_options(&self, msg: &DhcpMessage, ip: Ipv4Addr, lease_time: u32) -> Vec<DhcpOption> {
+        let requested = msg.parameter_request_list();
+        let mut opts = self.config.lease_options(lease_time, ip, requested);
+        if let Some(reservation) = self.reservation_for(msg) {
+            reservation.apply_options(&mut opts, requested);
+        }
+        opts
+    }
+
+    // Le client a retenu l'offre d'un autre serveur : la nôtre est retirée et son
+    // adresse rendue au pool sans attendre l'expiration du délai de réservation
+    fn withdraw_offer(&mut self, client_id: &str) {
+        let offered = self.leases.get(client_id).is_some_and(|lease| lease.state == LeaseState::Offered);
+        if !offered {
+            return;
+        }
+        if let Some(mut lease) = self.leases.remove(client_id) {
+            match lease.withdraw_offer() {
+                Ok(status) => {
+                    println!("↩️ Offre {} retirée pour {}, adresse remise dans le pool", lease.ip, client_id);
+                    self.close_lease(lease, status);
+                }
+                Err(e) => println!("❌ {}", e),
+            }
+        }
+    }
+
+    // Remet l'adresse d'un bail terminé dans le pool et enregistre son statut final
+    fn close_lease(&mut self, lease: Lease, status: &'static str) {
+        self.pool.release_ip(&lease.ip);
+        self.store.lease_closed(&lease, status);
+    }
+
+    fn send_offer(&self, msg: &DhcpMessage, ip: Ipv4Addr, src: SocketAddr) {
+        let mut offer = msg.reply(MessageType::Offer);
+        offer.yiaddr = ip;
+        let lease_time = self.config.grant_lease_time(msg.lease_time());
+        offer.options.extend(self.reply_options(msg, ip, lease_time));
+        self.send_reply(&offer, src);
+    }
+
+    // Refus d'un REQUEST (RFC 2131, 4.3.2) : ni adresse ni paramètres, seulement l'identifiant serveur
+    fn send_nak(&self, msg: &DhcpMessage, src: SocketAddr, reason: &str) {
+        let mut nak = msg.reply(MessageType::Nak);
+        // Le relais ne peut pas joindre en unicast un client sans adresse valide :
+        // il doit diffuser le NAK (RFC 2131, 4.3.2)
+        if !msg.giaddr.is_unspecified() {
+            nak.flags |= FLAG_BROADCAST;
+        }
+        nak.set_option(DhcpOption::ServerIdentifier(self.config.server_ip));
+        nak.set_option(DhcpOption::Message(reason.to_string()));
+        self.send_reply(&nak, src);
+    }
+
+    // Relais, ciaddr, chaddr ou diffusion selon les champs de la réponse (RFC 2131, 4.1)
+    fn send_reply(&self, reply: &DhcpMessage, src: SocketAddr) {
+        let data = reply.to_bytes();
+        let dest = match (reply.reply_destination(src, self.config.relay_port), &self.link) {
+            (ReplyDestination::Ip(dest), _) => dest,
+            (ReplyDestination::Link { chaddr, addr }, Some(link)) => match link.send(chaddr, addr, &data) {
+                Ok(()) => return,
+                Err(e) => {
+                    eprintln!("Erreur envoi vers {} : {}, réponse diffusée", addr, e);
+                    SocketAddr::new(Ipv4Addr::BROADCAST.into(), CLIENT_PORT)
+                }
+            },
+            (ReplyDestination::Link { .. }, None) => SocketAddr::new(Ipv4Addr::BROADCAST.into(), CLIENT_PORT),
+        };
+        if let Err(e) = self.socket.send_to(&data, dest) {
+            eprintln!("Erreur envoi vers {}: {}", dest, e);
+        }
+    }
+
+    pub fn handle_message(&mut self, buf: &[u8], src: SocketAddr) {
+        let msg = match DhcpMessage::parse(buf) {
+            Ok(msg) => msg,
+            Err(e) => {
+                println!("❌ Message DHCP invalide de {} : {}", src, e);
+                return;
+            }
+        };
+        if msg.op != BOOTREQUEST {
+            return;
+        }
+
+        match msg.message_type() {
+            Some(MessageType::Discover) => self.handle_discover(&msg, src),
+            Some(MessageType::Request) => self.handle_request(&msg, src),
+            Some(MessageType::Release) => self.handle_release(&msg, src),
+            Some(MessageType::Decline) => self.handle_decline(&msg, src),
+            Some(MessageType::Inform) => self.handle_inform(&msg, src),
+            Some(other) => println!("⚠️ Message {} ignoré de {}", other, src),
+            None => println!("❌ Message sans type DHCP (option 53) de {}", src),
+        }
+    }
+
+    fn handle_discover(&mut self, msg: &DhcpMessage, src: SocketAddr) {
+        let mac = msg.mac_string();
+        let client_id = msg.client_id();
+        let hold = Duration::from_secs(self.config.offer_hold_time);
+        println!("\n\n ******** DORA ******** ");
+        println!("⬅️ DISCOVER reçu de {} avec MAC {} (xid {:08x})", src, mac, msg.xid);
+
+        // Sous-réseau du client : celui désigné par le relais (link-selection ou giaddr)
+        // ou le réseau local
+        let subnet = match self.config.select_subnet(msg.link_address()) {
+            Some(subnet) => subnet,
+            None => {
+                println!("⚠️ Relais {} inconnu, DISCOVER ignoré", msg.giaddr);
+                return;
+            }
+        };
+        if !msg.giaddr.is_unspecified() {
+            println!("📡 DISCOVER relayé par {} (sous-réseau {})", msg.giaddr, subnet);
+        }
+        if let Some(info) = msg.relay_agent_info() {
+            println!("   Option 82 : {}", info);
+        }
+
+        // Une réservation prime sur l'adresse que le client détenait jusque-là ; un bail
+        // sur un autre sous-réseau (client déplacé) est lui aussi abandonné
+        let reserved_ip = self.reservation_for(msg).map(|r| r.ip).filter(|ip| subnet.contains(*ip));
+        let stale = self
+            .leases
+            .get(&client_id)
+            .is_some_and(|lease| !subnet.contains(lease.ip) || reserved_ip.is_some_and(|ip| ip != lease.ip));
+        if stale {
+            if let Some(mut old) = self.leases.remove(&client_id) {
+                if let Ok(status) = old.transition(LeaseState::Released) {
+                    self.close_lease(old, status);
+                }
+            }
+        }
+
+        // Client déjà connu (offre en cours ou bail actif) : on lui repropose la même adresse
+        // sans puiser dans le pool, qu'il s'agisse d'une retransmission ou d'une nouvelle transaction
+        if let Some(lease) = self.leases.get_mut(&client_id) {
+            if lease.xid == msg.xid {
+                println!("🔁 Retransmission du DISCOVER, renvoi de l'OFFER {} à {}", lease.ip, src);
+            } else {
+                println!("🔁 Client déjà connu, nouvelle offre de son adresse {} à {}", lease.ip, src);
+            }
+            lease.addr = src;
+            lease.xid = msg.xid;
+            lease.relay_agent = msg.relay_agent_info().cloned();
+            if lease.state == LeaseState::Offered {
+                lease.offer_deadline = Some(Instant::now() + hold);
+            }
+            let ip = lease.ip;
+            self.send_offer(msg, ip, src);
+            return;
+        }
+
+        // Le ping d'une adresse pour ce client est en cours : l'OFFER partira à son résultat
+        if self.probing.values().any(|pending| pending.msg.client_id() == client_id) {
+            println!("🔁 Retransmission du DISCOVER, ping en cours pour {}", client_id);
+            return;
+        }
+
+        // Adresse réservée, sauf si un autre client l'occupe encore ; sinon le pool dynamique
+        let reserved_ip = reserved_ip.filter(|ip| {
+            let taken = self.leases.values().any(|lease| lease.ip == *ip);
+            if taken {
+                println!("⚠️ Adresse réservée {} encore louée à un autre client", ip);
+            }
+            !taken
+        });
+        // Une adresse réservée n'est pas sondée : l'hôte peut déjà l'avoir configurée
+        if let Some(ip) = reserved_ip {
+            self.make_offer(msg, ip, src, None);
+            return;
+        }
+        // Puis la dernière adresse détenue par le client, si elle est libre
+        let previous_ip = self
+            .previous_ips
+            .get(&client_id)
+            .or_else(|| self.previous_ips.get(&mac))
+            .copied()
+            .filter(|ip| subnet.contains(*ip) && self.pool.take_ip(*ip));
+        match previous_ip.or_else(|| self.pool.lease_ip_for(subnet, &mac)) {
+            Some(ip) => {
+                let pending = PendingOffer { msg: msg.clone(), src, subnet, previous_ip, conflicts: 0 };
+                self.probe_or_offer(pending, ip);
+            }
+            None => {
+                // Pas de réponse : le client retentera son DISCOVER (RFC 2131, 4.3.1)
+                println!("⚠️ Pas d'IP disponible pour {}", src);
+            }
+        }
+    }
+
+    // Crée l'offre de `ip` pour le client et lui envoie l'OFFER
+    fn make_offer(&mut self, msg: &DhcpMessage, ip: Ipv4Addr, src: SocketAddr, previous_ip: Option<Ipv4Addr>) {
+        let mac = msg.mac_string();
+        let client_id = msg.client_id();
+        let hold = Duration::from_secs(self.config.offer_hold_time);
+        let vendor = lookup_oui(&mac);
+        if self.reservation_for(msg).is_some_and(|r| r.ip == ip) {
+            println!("📌 Adresse réservée {} pour {}", ip, client_id);
+        } else if previous_ip == Some(ip) {
+            println!("🧲 Adresse précédente {} reproposée à {}", ip, client_id);
+        }
+        println!("➡️ Envoi OFFER {} à {} (Marque: {})", ip, src, vendor);
+        let mut lease = Lease::offer(&client_id, &mac, ip, src, msg.xid, hold);
+        lease.relay_agent = msg.relay_agent_info().cloned();
+        self.store.lease_created(&lease, vendor);
+        self.leases.insert(client_id, lease);
+        self.send_offer(msg, ip, src);
+    }
+
+    // Motif du NAK si l'adresse demandée ne peut pas être accordée (RFC 2131, 4.3.2) :
+    // hors du sous-réseau, occupée, ou sans offre ni bail en cours pour ce client
+    fn check_request(&self, msg: &DhcpMessage, ip: Ipv4Addr, subnet: Subnet) -> Result<(), &'static str> {
+        let client_id = msg.client_id();
+        if !subnet.contains(ip) {
+            return Err("adresse hors du sous-réseau");
+        }
+        let in_use = self.leases.values().any(|lease| lease.ip == ip && lease.client_id != client_id);
+        if in_use || self.quarantine.contains_key(&ip) {
+            return Err("adresse déjà utilisée");
+        }
+        if let Some(reservation) = self.reservation_for(msg) {
+            return if reservation.ip == ip {
+                Ok(())
+            } else {
+                Err("une autre adresse est réservée pour ce client")
+            };
+        }
+        if self.reservations.iter().any(|r| r.ip == ip) {
+            return Err("adresse réservée à un autre client");
+        }
+        match self.leases.get(&client_id) {
+            Some(lease) if lease.ip == ip => {
+                if lease.offer_expired(Instant::now()) {
+                    Err("offre expirée")
+                } else if lease.lease_expired(Utc::now()) {
+                    Err("bail expiré")
+                } else {
+                    Ok(())
+                }
+            }
+            _ if !self.pool.contains(ip) => Err("adresse hors du pool"),
+            _ => Err("aucune offre ni bail en cours pour cette adresse"),
+        }
+    }
+
+    fn handle_request(&mut self, msg: &DhcpMessage, src: SocketAddr) {
+        let mac = msg.mac_string();
+        let client_id = msg.client_id();
+        let (state, requested_ip) = match msg.request_state() {
+            Some(request) => request,
+            None => {
+                println!("❌ REQUEST sans adresse demandée de {}", src);
+                return;
+            }
+        };
+        // RENEWING et REBINDING ne se distinguent pas au niveau UDP : on se fie à l'échéance T2 du bail
+        let label = match self.leases.get(&client_id) {
+            Some(lease) if state == RequestState::Renewing && lease.rebinding_due(Utc::now()) => "REBINDING".to_string(),
+            _ => state.to_string(),
+        };
+        println!("⬅️ REQUEST {} ({}) reçu de {} avec MAC {}", requested_ip, label, src, mac);
+
+        // Sous-réseau du client : celui du relais, celui de ciaddr pour une prolongation
+        // reçue directement, sinon le réseau local
+        let subnet = if msg.giaddr.is_unspecified() && state == RequestState::Renewing {
+            self.config.subnet_for(requested_ip)
+        } else {
+            self.config.select_subnet(msg.link_address())
+        };
+        // Relais inconnu ou adresse hors de tout sous-réseau desservi : ce réseau relève
+        // d'un autre serveur, dont les baux ne doivent pas être refusés
+        let subnet = match subnet {
+            Some(subnet) => subnet,
+            None => {
+                println!("⚠️ Aucun sous-réseau desservi pour {} (relais {}), REQUEST ignoré", requested_ip, msg.giaddr);
+                return;
+            }
+        };
+
+        match state {
+            RequestState::Selecting => {
+                let server_id = msg.server_identifier();
+                if server_id != Some(self.config.server_ip) {
+                    println!("ℹ️ {} a retenu un autre serveur ({:?}), REQUEST ignoré", client_id, server_id);
+                    self.withdraw_offer(&client_id);
+                    return;
+                }
+            }
+            // Hors SELECTING, un client inconnu relève peut-être d'un autre serveur : silence,
+            // sauf adresse hors du sous-réseau (RFC 2131, 4.3.2)
+            _ => {
+                let known = self.leases.contains_key(&client_id) || self.reservation_for(msg).is_some();
+                if !known && subnet.contains(requested_ip) {
+                    println!("🤐 Aucun bail connu pour {} ({}), REQUEST ignoré", client_id, label);
+                    return;
+                }
+            }
+        }
+
+        if let Err(reason) = self.check_request(msg, requested_ip, subnet) {
+            println!("❌ REQUEST {} refusé ({}), envoi NAK à {}", requested_ip, reason, src);
+            self.send_nak(msg, src, reason);
+            return;
+        }
+
+        let vendor = lookup_oui(&mac);
+        let lease_time = self.config.grant_lease_time(msg.lease_time());
+        let mut lease = match self.leases.remove(&client_id) {
+            Some(lease) if lease.ip == requested_ip => lease,
+            previous => {
+                // Le client demande une autre adresse que celle qu'il détenait : l'ancienne est libérée
+                if let Some(mut old) = previous {
+                    if let Ok(status) = old.transition(LeaseState::Released) {
+                        self.close_lease(old, status);
+                    }
+                }
+                Lease::offer(&client_id, &mac, requested_ip, src, msg.xid, Duration::ZERO)
+            }
+        };
+        lease.addr = src;
+        lease.xid = msg.xid;
+        lease.relay_agent = msg.relay_agent_info().cloned();
+        // Un bail déjà confirmé est prolongé, une offre est confirmée
+        let renewal = lease.state == LeaseState::Bound;
+        let result = if renewal { lease.renew(lease_time) } else { lease.bind(lease_time) };
+        if let Err(e) = result {
+            println!("❌ {}", e);
+            self.leases.insert(client_id, lease);
+            return;
+        }
+        if renewal {
+            println!("🔄 Bail {} de {} prolongé de {} s ({})", requested_ip, client_id, lease_time, label);
+        }
+        println!("➡️ Envoi ACK {} à {} (Marque: {})", requested_ip, src, vendor);
+
+        self.previous_ips.insert(client_id.clone(), requested_ip);
+        self.previous_ips.insert(mac, requested_ip);
+        if renewal {
+            self.store.lease_renewed(&lease, vendor);
+        } else {
+            self.store.lease_bound(&lease, vendor);
+        }
+        self.leases.insert(client_id, lease);
+
+        let mut ack = msg.reply(MessageType::Ack);
+        ack.ciaddr = msg.ciaddr;
+        ack.yiaddr = requested_ip;
+        ack.options.extend(self.reply_options(msg, requested_ip, lease_time));
+        self.send_reply(&ack, src);
+    }
+
+    fn handle_release(&mut self, msg: &DhcpMessage, src: SocketAddr) {
+        let client_id = msg.client_id();
+        println!("\n\n⬅️ RELEASE reçu de {} (client {})", src, client_id);
+        if msg.server_identifier() != Some(self.config.server_ip) {
+            println!("⚠️ RELEASE adressé à un autre serveur ({:?}), ignoré", msg.server_identifier());
+            return;
+        }
+
+        // Seul le détenteur du bail peut le libérer : ciaddr et chaddr doivent correspondre (RFC 2131, 4.4.6)
+        let mac = msg.mac_string();
+        let mut lease = match self.leases.remove(&client_id) {
+            Some(lease) if lease.ip == msg.ciaddr && lease.mac == mac => lease,
+            other => {
+                if let Some(lease) = other {
+                    self.leases.insert(client_id, lease);
+                }
+                println!("⚠️ Aucun bail {} pour la MAC {}, RELEASE de {} ignoré", msg.ciaddr, mac, src);
+                return;
+            }
+        };
+        match lease.transition(LeaseState::Released) {
+            Ok(status) => {
+                println!("🔁 IP {} libérée par {} (MAC {})", lease.ip, src, lease.mac);
+                self.close_lease(lease, status);
+            }
+            Err(e) => {
+                println!("❌ {}", e);
+                self.leases.insert(client_id, lease);
+            }
+        }
+    }
+
+    // Hôte configuré statiquement qui ne demande que ses paramètres (RFC 2131, 4.3.5) :
+    // ACK sans adresse ni durée de bail, le pool et les baux restent inchangés
+    fn handle_inform(&self, msg: &DhcpMessage, src: SocketAddr) {
+        println!("\n⬅️ INFORM reçu de {} (ciaddr {}, MAC {})", src, msg.ciaddr, msg.mac_string());
+        let requested = msg.parameter_request_list();
+        let mut ack = msg.reply(MessageType::Ack);
+        ack.ciaddr = msg.ciaddr;
+        ack.options.extend(self.config.inform_options(msg.ciaddr, requested));
+        if let Some(reservation) = self.reservation_for(msg) {
+            reservation.apply_options(&mut ack.options, requested);
+        }
+        println!("➡️ Envoi ACK (paramètres seuls) à {}", src);
+        self.send_reply(&ack, src);
+    }
+
+    // Le client a détecté que l'adresse reçue est déjà utilisée (RFC 2131, 4.3.3) :
+    // le bail est clos et l'adresse mise en quarantaine
+    fn handle_decline(&mut self, msg: &DhcpMessage, src: SocketAddr) {
+        let client_id = msg.client_id();
+        let declined = msg.requested_ip();
+        println!("\n⬅️ DECLINE {} reçu de {} (client {})",
+            declined.map_or("?".to_string(), |ip| ip.to_string()), src, client_id);
+        if msg.server_identifier() != Some(self.config.server_ip) {
+            println!("⚠️ DECLINE adressé à un autre serveur ({:?}), ignoré", msg.server_identifier());
+            return;
+        }
+
+        // Comme pour RELEASE, seul le détenteur du bail (même chaddr) peut le refuser
+        let mac = msg.mac_string();
+        let mut lease = match self.leases.remove(&client_id) {
+            Some(lease) if Some(lease.ip) == declined && lease.mac == mac => lease,
+            other => {
+                if let Some(lease) = other {
+                    self.leases.insert(client_id, lease);
+                }
+                println!("⚠️ DECLINE ignoré : le client ne détient pas cette adresse");
+                return;
+            }
+        };
+        match lease.transition(LeaseState::Declined) {
+            Ok(status) => {
+                println!("⛔ {} refusée par {} : adresse en quarantaine pour {} s", lease.ip, client_id, self.config.decline_probation);
+                self.quarantine_ip(lease.ip, self.config.decline_probation);
+                self.store.lease_closed(&lease, status);
+            }
+            Err(e) => {
+                println!("❌ {}", e);
+                self.leases.insert(client_id, lease);
+            }
+        }
+    }
+
+    /// Libère les adresses offertes dont le client n'a pas envoyé de REQUEST à temps.
+    pub fn expire_offers(&mut self) {
+        let now = Instant::now();
+        let expired: Vec<String> = self
+            .leases
+            .values()
+            .filter(|lease| lease.offer_expired(now))
+            .map(|lease| lease.client_id.clone())
+            .collect();
+
+        for client_id in expired {
+            if let Some(mut lease) = self.leases.remove(&client_id) {
+                match lease.transition(LeaseState::Expired) {
+                    Ok(status) => {
+                        println!("⌛ Offre {} expirée pour {} (MAC {}), adresse remise dans le pool", lease.ip, client_id, lease.mac);
+                        self.close_lease(lease, status);
+                    }
+                    Err(e) => println!("❌ {}", e),
+                }
+            }
+        }
+    }
+
+    // Confie `ip` au thread de ping ; sans lui, l'OFFER part tout de suite
+    fn probe_or_offer(&mut self, pending: PendingOffer, ip: Ipv4Addr) {
+        match &self.probe_tx {
+            Some(tx) if tx.send(ip).is_ok() => {
+                println!("🔍 Ping de {} avant l'OFFER à {}", ip, pending.src);
+                self.probing.insert(ip, pending);
+            }
+            _ => self.make_offer(&pending.msg, ip, pending.src, pending.previous_ip),
+        }
+    }
+
+    // Résultat du ping de `ip` : l'OFFER part, ou l'adresse est abandonnée et la suivante sondée
+    fn probe_done(&mut self, ip: Ipv4Addr, in_use: bool) {
+        let mut pending = match self.probing.remove(&ip) {
+            Some(pending) => pending,
+            None => return,
+        };
+        // Le client a obtenu une adresse entre-temps (INIT-REBOOT, par exemple) : la candidate est rendue
+        if self.leases.contains_key(&pending.msg.client_id()) {
+            self.pool.release_ip(&ip);
+            return;
+        }
+        if !in_use {
+            self.make_offer(&pending.msg, ip, pending.src, pending.previous_ip);
+            return;
+        }
+        self.abandon(ip, &pending.msg, pending.src);
+        pending.conflicts += 1;
+        if pending.conflicts >= MAX_PROBES {
+            println!("⚠️ Trop d'adresses en conflit, DISCOVER de {} sans réponse", pending.src);
+            return;
+        }
+        match self.pool.lease_ip_for(pending.subnet, &pending.msg.mac_string()) {
+            Some(next) => self.probe_or_offer(pending, next),
+            None => println!("⚠️ Pas d'IP disponible pour {}", pending.src),
+        }
+    }
+
+    // Écarte une adresse déjà utilisée sur le réseau et l'enregistre comme abandonnée
+    fn abandon(&mut self, ip: Ipv4Addr, msg: &DhcpMessage, src: SocketAddr) {
+        println!("⛔ {} répond au ping : adresse abandonnée pour {} s", ip, self.config.abandon_time);
+        self.quarantine_ip(ip, self.config.abandon_time);
+
+        let mut lease = Lease::offer(&msg.client_id(), &msg.mac_string(), ip, src, msg.xid, Duration::ZERO);
+        if lease.transition(LeaseState::Abandoned).is_ok() {
+            self.store.lease_created(&lease, lookup_oui(&lease.mac));
+        }
+    }
+
+    // L'adresse reste attribuée dans le pool jusqu'à la fin de la quarantaine
+    fn quarantine_ip(&mut self, ip: Ipv4Addr, secs: u64) {
+        self.quarantine.insert(ip, Instant::now() + Duration::from_secs(secs));
+    }
+
+    /// Remet dans le pool les adresses dont la quarantaine est terminée.
+    pub fn expire_quarantine(&mut self) {
+        let now = Instant::now();
+        let expired: Vec<Ipv4Addr> = self
+            .quarantine
+            .iter()
+            .filter(|(_, until)| **until <= now)
+            .map(|(ip, _)| *ip)
+            .collect();
+
+        for ip in expired {
+            self.quarantine.remove(&ip);
+            self.pool.release_ip(&ip);
+            println!("⌛ Fin de quarantaine pour {}, adresse remise dans le pool", ip);
+        }
+    }
+
+    /// Libère les baux confirmés dont la date de fin est dépassée.
+    pub fn expire_leases(&mut self) {
+        let now = Utc::now();
+        let expired: Vec<String> = self
+            .leases
+            .values()
+            .filter(|lease| lease.lease_expired(now))
+            .map(|lease| lease.client_id.clone())
+            .collect();
+
+        for client_id in expired {
+            if let Some(mut lease) = self.leases.remove(&client_id) {
+                match lease.transition(LeaseState::Expired) {
+                    Ok(status) => {
+                        println!("⌛ Bail {} expiré pour {} (MAC {}), adresse remise dans le pool", lease.ip, client_id, lease.mac);
+                        self.close_lease(lease, status);
+                    }
+                    Err(e) => println!("❌ {}", e),
+                }
+            }
+        }
+    }
+
+    pub fn afficher_clients(&self) {
+        println!("📋 Clients connectés :");
+        for lease in self.leases.values() {
+            let vendor = lookup_oui(&lease.mac);
+            let fin = match lease.end_time {
+                Some(end) => format!("fin du bail {}", end.format("%Y-%m-%d %H:%M:%S UTC")),
+                None => "offre en attente".to_string(),
+            };
+            let relais = lease.relay_agent.as_ref().map_or(String::new(), |info| format!(", {}", info));
+            println!(
+                "🔹 {} => {} [{}] (MAC: {}, Marque: {}, vu depuis {}{}, {})",
+                lease.client_id, lease.ip, lease.state, lease.mac, vendor, lease.addr, relais, fin
+            );
+        }
+        let now = Instant::now();
+        for (ip, until) in &self.quarantine {
+            println!("⛔ {} en quarantaine encore {} s", ip, until.saturating_duration_since(now).as_secs());
+        }
+    }
+
+    // Retrouve un client par identifiant, adresse MAC (avec ou sans séparateurs) ou IP louée
+    fn find_client(&self, client_input: &str) -> Option<String> {
+        let wanted = client_input.replace([':', '-'], "").to_uppercase();
+        self.leases
+            .values()
+            .find(|lease| lease.client_id == wanted || lease.mac == wanted || lease.ip.to_string() == client_input)
+            .map(|lease| lease.client_id.clone())
+    }
+
+    pub fn supprimer_client(&mut self, client_input: &str) {
+        let found = self
+            .find_client(client_input)
+            .and_then(|id| self.leases.remove(&id));
+
+        match found {
+            Some(mut lease) => match lease.release_by_admin() {
+                Ok(status) => {
+                    // Notifier le client (DHCPFORCERENEW, RFC 3203)
+                    if let Some(mac_bytes) = parse_mac(&lease.mac) {
+                        let mut notice = DhcpMessage::new(BOOTREPLY, MessageType::ForceRenew, 0, mac_bytes);
+                        notice.ciaddr = lease.ip;
+                        self.send_reply(&notice, lease.addr);
+                    }
+
+                    println!("✅ Client {} supprimé. IP {} libérée.", lease.client_id, lease.ip);
+                    self.close_lease(lease, status);
+                }
+                Err(e) => println!("❌ {}", e),
+            },
+            None => println!("⚠️ Aucun client trouvé pour « {} ».", client_input),
+        }
+    }
+
+    pub fn afficher_reservations(&self) {
+        println!("📌 Réservations :");
+        for r in &self.reservations {
+            let occupe = self.leases.values().find(|lease| lease.ip == r.ip);
+            let relais = r.remote_id.as_deref().map_or(String::new(), |remote| format!("@{}", remote));
+            println!(
+                "🔸 {}{} => {} (nom: {}, {} option(s), {})",
+                r.owner(),
+                relais,
+                r.ip,
+                r.hostname.as_deref().unwrap_or("-"),
+                r.options.len(),
+                occupe.map_or("libre".to_string(), |lease| format!("{} [{}]", lease.client_id, lease.state))
+            );
+        }
+    }
+
+    /// Ajoute une réservation : `client` est une adresse MAC, un port de relais
+    /// (`circuit:<circuit-id>[@<remote-id>]`) ou, à défaut, un identifiant client (hex).
+    /// `options` liste les options propres à l'hôte (voir `parse_option_list`).
+    pub fn ajouter_reservation(&mut self, client: &str, ip: &str, hostname: &str, options: &str) {
+        let ip: Ipv4Addr = match ip.parse() {
+            Ok(ip) => ip,
+            Err(_) => {
+                println!("❌ Adresse IP invalide : {}", ip);
+                return;
+            }
+        };
+        let (circuit_id, remote_id) = match client.strip_prefix("circuit:") {
+            Some(port) => match port.split_once('@') {
+                Some((circuit, remote)) => (Some(circuit.to_string()), Some(remote.to_string())),
+                None => (Some(port.to_string()), None),
+            },
+            None => (None, None),
+        };
+        let client = client.replace([':', '-'], "").to_uppercase();
+        let (mac, client_id) = match parse_mac(&client) {
+            _ if circuit_id.is_some() => (None, None),
+            Some(_) => (Some(client), None),
+            None if !client.is_empty() => (None, Some(client)),
+            None => {
+                println!("❌ Client manquant.");
+                return;
+            }
+        };
+        if circuit_id.as_deref().is_some_and(str::is_empty) {
+            println!("❌ Circuit manquant.");
+            return;
+        }
+        let options = match parse_option_list(options) {
+            Ok(options) => options,
+            Err(entry) => {
+                println!("❌ Option invalide : {}", entry);
+                return;
+            }
+        };
+        let reservation = Reservation {
+            mac,
+            client_id,
+            circuit_id,
+            remote_id,
+            ip,
+            hostname: Some(hostname.to_string()).filter(|h| !h.is_empty()),
+            options,
+        };
+        if let Some(existing) = self
+            .reservations
+            .iter()
+            .find(|r| {
+                r.ip == ip
+                    || r.matches(reservation.owner(), reservation.owner())
+                    || (r.circuit_id.is_some() && r.circuit_id == reservation.circuit_id && r.remote_id == reservation.remote_id)
+            })
+        {
+            println!("❌ Déjà réservé : {} => {}", existing.owner(), existing.ip);
+            return;
+        }
+        if let Some(lease) = self.leases.values().find(|lease| lease.ip == ip && lease.client_id != reservation.owner()) {
+            println!("⚠️ {} est encore louée à {} ; elle sera réservée à sa libération.", ip, lease.client_id);
+        }
+
+        self.pool.exclude(ip);
+        println!("✅ Adresse {} réservée pour {}.", ip, reservation.owner());
+        self.store.reservation_added(&reservation);
+        self.reservations.push(reservation);
+    }
+
+    pub fn supprimer_reservation(&mut self, input: &str) {
+        let wanted = input.replace([':', '-'], "").to_uppercase();
+        let circuit = input.strip_prefix("circuit:").unwrap_or(input);
+        let position = self.reservations.iter().position(|r| {
+            r.ip.to_string() == input || r.matches(&wanted, &wanted) || r.circuit_id.as_deref() == Some(circuit)
+        });
+        match position {
+            Some(position) => {
+                let reservation = self.reservations.remove(position);
+                let ip = reservation.ip;
+                self.return_to_pool(ip);
+                println!("✅ Réservation {} => {} supprimée.", reservation.owner(), ip);
+                self.store.reservation_removed(ip);
+            }
+            None => println!("⚠️ Aucune réservation trouvée pour « {} ».", input),
+        }
+    }
+
+    pub fn afficher_exclusions(&self) {
+        println!("🚫 Exclusions :");
+        for &(start, end) in &self.config.exclusions {
+            println!("🔸 {} - {} (configuration)", start, end);
+        }
+        for &(start, end) in &self.exclusions {
+            println!("🔸 {} - {}", start, end);
+        }
+    }
+
+    /// `input` : plage « début-fin » ou adresse seule.
+    pub fn ajouter_exclusion(&mut self, input: &str) {
+        let range = match parse_range(input).filter(|(start, end)| start <= end) {
+            Some(range) => range,
+            None => {
+                println!("❌ Plage invalide : {}", input);
+                return;
+            }
+        };
+        if self.exclusions.contains(&range) {
+            println!("⚠️ Exclusion déjà présente.");
+            return;
+        }
+        let (start, end) = range;
+        for lease in self.leases.values().filter(|lease| start <= lease.ip && lease.ip <= end) {
+            println!("⚠️ {} est encore louée à {} ; elle ne sera plus attribuée après sa libération.", lease.ip, lease.client_id);
+        }
+
+        self.pool.exclude_range(start, end);
+        self.exclusions.push(range);
+        println!("✅ Plage {} - {} exclue ({} adresses libres).", start, end, self.pool.available());
+        self.store.exclusion_added(range);
+    }
+
+    pub fn supprimer_exclusion(&mut self, input: &str) {
+        let position = parse_range(input).and_then(|range| self.exclusions.iter().position(|r| *r == range));
+        match position {
+            Some(position) => {
+                let range = self.exclusions.remove(position);
+                let (start, end) = range;
+                let subnets: Vec<Subnet> = self.pool.pools().iter().map(IpPool::subnet).collect();
+                for (first_host, last_host) in subnets.iter().map(Subnet::host_range) {
+                    for ip in u32::from(start.max(first_host))..=u32::from(end.min(last_host)) {
+                        self.return_to_pool(Ipv4Addr::from(ip));
+                    }
+                }
+                println!("✅ Exclusion {} - {} supprimée ({} adresses libres).", start, end, self.pool.available());
+                self.store.exclusion_removed(range);
+            }
+            None => println!("⚠️ Aucune exclusion « {} » (les exclusions de la configuration ne se suppriment pas ici).", input),
+        }
+    }
+}
+
+/// Démarre la détection de conflits (RFC 2131, 2.2) si DHCP_PING_TIMEOUT_MS
+/// l'active. Les pings ont lieu dans un thread dédié, hors du verrou de `state` :
+/// seul l'OFFER du client concerné attend leur résultat. Les adresses arrivées
+/// pendant un ping sont sondées ensemble au tour suivant.
+pub fn spawn_prober<S: LeaseStore + 'static>(state: &Arc<Mutex<DhcpServer<S>>>) {
+    let timeout = match state.lock().unwrap().config.ping_timeout_ms {
+        0 => return,
+        ms => Duration::from_millis(ms),
+    };
+    let mut probe = match ConflictProbe::new(timeout) {
+        Ok(probe) => probe,
+        Err(e) => {
+            println!("⚠️ Détection de conflits (ping) désactivée : {}", e);
+            return;
+        }
+    };
+    let (tx, rx) = mpsc::channel::<Ipv4Addr>();
+    state.lock().unwrap().probe_tx = Some(tx);
+
+    let state = Arc::clone(state);
+    thread::spawn(move || {
+        while let Ok(ip) = rx.recv() {
+            let mut batch = vec![ip];
+            batch.extend(rx.try_iter());
+            let answered = probe.responding(&batch).unwrap_or_else(|e| {
+                eprintln!("Erreur ping {:?}: {}", batch, e);
+                Vec::new()
+            });
+            let mut st = state.lock().unwrap();
+            for ip in batch {
+                st.probe_done(ip, answered.contains(&ip));
+            }
+        }
+    });
+}
@@ -0,0 +1,52 @@
+use std::net::Ipv4Addr;
+
+use crate::lease::Lease;
+use crate::reservation::Reservation;
+
+/// Persistance des événements du serveur. Chaque appel décrit un changement déjà
+/// appliqué en mémoire ; par défaut, il n'est pas conservé.
+pub trait LeaseStore: Send {
+    /// Nouveau bail : offre, ou adresse abandonnée après un conflit.
+    fn lease_created(&mut self, _lease: &Lease, _vendor: &str) {}
+
+    /// Offre confirmée par un ACK.
+    fn lease_bound(&mut self, _lease: &Lease, _vendor: &str) {}
+
+    /// Bail confirmé prolongé (RENEWING, REBINDING ou INIT-REBOOT).
+    fn lease_renewed(&mut self, _lease: &Lease, _vendor: &str) {}
+
+    /// Bail terminé (libéré, expiré, refusé...) avec son statut final.
+    fn lease_closed(&mut self, _lease: &Lease, _status: &'static str) {}
+
+    fn reservation_added(&mut self, _reservation: &Reservation) {}
+
+    fn reservation_removed(&mut self, _ip: Ipv4Addr) {}
+
+    fn exclusion_added(&mut self, _range: (Ipv4Addr, Ipv4Addr)) {}
+
+    fn exclusion_removed(&mut self, _range: (Ipv4Addr, Ipv4Addr)) {}
+}
+
+/// Historique en mémoire : l'état de chaque bail à chaque transition.
+#[derive(Debug, Default)]
+pub struct HistoryStore {
+    pub leases: Vec<Lease>,
+}
+
+impl LeaseStore for HistoryStore {
+    fn lease_created(&mut self, lease: &Lease, _vendor: &str) {
+        self.leases.push(lease.clone());
+    }
+
+    fn lease_bound(&mut self, lease: &Lease, _vendor: &str) {
+        self.leases.push(lease.clone());
+    }
+
+    fn lease_renewed(&mut self, lease: &Lease, _vendor: &str) {
+        self.leases.push(lease.clone());
+    }
+
+    fn lease_closed(&mut self, lease: &Lease, _status: &'static str) {
+        self.leases.push(lease.clone());
+    }
+}
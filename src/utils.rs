@@ -0,0 +1,40 @@
+use std::net::Ipv4Addr;
+
+/// Première adresse IPv4 non-loopback de la machine, utilisée comme identifiant serveur.
+pub fn local_ipv4() -> Option<Ipv4Addr> {
+    get_if_addrs::get_if_addrs()
+        .ok()?
+        .into_iter()
+        .filter(|iface| !iface.is_loopback())
+        .find_map(|iface| match iface.ip() {
+            std::net::IpAddr::V4(ip) => Some(ip),
+            _ => None,
+        })
+}
+
+/// Marque de l'équipement d'après l'OUI de son adresse MAC (table simplifiée).
+pub fn lookup_oui(mac: &str) -> &'static str {
+    let oui_map = [
+        ("3C5A37", "Apple"),
+        ("FCFBFB", "Samsung"),
+        ("A4C138", "Dell"),
+        ("00163E", "Cisco"),
+        ("001A2B", "Hewlett-Packard"),
+        ("F4F5E8", "Sony"),
+        ("F0DE61", "Microsoft"),
+        ("3C5AB4", "Apple"),
+        ("B827EB", "Raspberry Pi Foundation"),
+    ];
+
+    let mac = mac.to_uppercase().replace(":", "").replace("-", "");
+    if mac.len() < 6 {
+        return "Unknown";
+    }
+    let prefix = &mac[0..6];
+    for (oui, vendor) in oui_map.iter() {
+        if *oui == prefix {
+            return vendor;
+        }
+    }
+    "Unknown"
+}
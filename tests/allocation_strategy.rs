@@ -0,0 +1,124 @@
+// Stratégies d'allocation du pool (DHCP_ALLOCATION, ou propre à un sous-réseau relayé).
+use std::collections::BTreeSet;
+use std::net::Ipv4Addr;
+
+use dhcp_demo::config::{RelayScope, ServerConfig};
+use dhcp_demo::ip_pool::{AllocationStrategy, IpPool, Subnet};
+
+fn new_pool(strategy: AllocationStrategy, last: u8) -> IpPool {
+    let subnet: Subnet = "192.168.1.0/24".parse().unwrap();
+    let range = (Ipv4Addr::new(192, 168, 1, 1), Ipv4Addr::new(192, 168, 1, last));
+    IpPool::new(subnet, &[range], &[]).unwrap().with_strategy(strategy)
+}
+
+fn host(last: u8) -> Ipv4Addr {
+    Ipv4Addr::new(192, 168, 1, last)
+}
+
+#[test]
+fn strategies_are_parsed_by_name() {
+    assert_eq!("sequential".parse(), Ok(AllocationStrategy::Sequential));
+    assert_eq!("Random".parse(), Ok(AllocationStrategy::Random));
+    assert_eq!("lrr".parse(), Ok(AllocationStrategy::LeastRecentlyReleased));
+    assert_eq!("least-recently-released".parse(), Ok(AllocationStrategy::LeastRecentlyReleased));
+    assert_eq!(" hash-of-mac ".parse(), Ok(AllocationStrategy::HashOfMac));
+    assert!("round-robin".parse::<AllocationStrategy>().is_err());
+    assert_eq!(AllocationStrategy::default(), AllocationStrategy::Sequential);
+}
+
+#[test]
+fn sequential_takes_the_lowest_free_address() {
+    let mut pool = new_pool(AllocationStrategy::Sequential, 10);
+    assert_eq!(pool.lease_ip_for("020000000001"), Some(host(1)));
+    assert_eq!(pool.lease_ip_for("020000000002"), Some(host(2)));
+    pool.release_ip(&host(1));
+    assert_eq!(pool.lease_ip_for("020000000003"), Some(host(1)));
+    assert_eq!(pool.lease_ip_for("020000000004"), Some(host(3)));
+}
+
+#[test]
+fn random_hands_out_every_address_once() {
+    let mut pool = new_pool(AllocationStrategy::Random, 254);
+    let leased: Vec<Ipv4Addr> = std::iter::from_fn(|| pool.lease_ip()).collect();
+    let distinct: BTreeSet<Ipv4Addr> = leased.iter().copied().collect();
+    assert_eq!(leased.len(), 254);
+    assert_eq!(distinct.len(), 254);
+    // L'ordre n'est pas celui de l'allocation séquentielle
+    assert!(!leased.is_sorted());
+}
+
+#[test]
+fn least_recently_released_prefers_unused_then_oldest_released() {
+    let mut pool = new_pool(AllocationStrategy::LeastRecentlyReleased, 4);
+    assert_eq!(pool.lease_ip(), Some(host(1)));
+    pool.release_ip(&host(1));
+
+    // Les adresses jamais attribuées passent avant celle qui vient d'être libérée
+    assert_eq!(pool.lease_ip(), Some(host(2)));
+    assert_eq!(pool.lease_ip(), Some(host(3)));
+    assert_eq!(pool.lease_ip(), Some(host(4)));
+    assert_eq!(pool.lease_ip(), Some(host(1)));
+
+    // Puis dans l'ordre des libérations
+    pool.release_ip(&host(3));
+    pool.release_ip(&host(2));
+    assert_eq!(pool.lease_ip(), Some(host(3)));
+    assert_eq!(pool.lease_ip(), Some(host(2)));
+    assert_eq!(pool.lease_ip(), None);
+
+    // Une adresse libérée puis reprise par `take_ip` est sautée
+    pool.release_ip(&host(4));
+    pool.release_ip(&host(1));
+    assert!(pool.take_ip(host(4)));
+    assert_eq!(pool.lease_ip(), Some(host(1)));
+}
+
+#[test]
+fn hash_of_mac_returns_the_same_address_to_a_client() {
+    let mut pool = new_pool(AllocationStrategy::HashOfMac, 254);
+    let macs: Vec<String> = (1..=20).map(|i| format!("0200000000{:02X}", i)).collect();
+    let first: Vec<Ipv4Addr> = macs.iter().map(|mac| pool.lease_ip_for(mac).unwrap()).collect();
+    for ip in &first {
+        pool.release_ip(ip);
+    }
+    // Dans un autre ordre, chaque client retombe sur son adresse
+    for (mac, ip) in macs.iter().zip(&first).rev() {
+        assert_eq!(pool.lease_ip_for(mac), Some(*ip));
+    }
+    // Les adresses ne se suivent pas
+    assert!(!first.is_sorted());
+
+    // Adresse occupée : la suivante libre, en revenant au début du pool si besoin
+    let mut pool = new_pool(AllocationStrategy::HashOfMac, 254);
+    assert!(pool.take_ip(first[0]));
+    let expected = if first[0] == host(254) { host(1) } else { Ipv4Addr::from(u32::from(first[0]) + 1) };
+    assert_eq!(pool.lease_ip_for(&macs[0]), Some(expected));
+}
+
+#[test]
+fn each_pool_has_its_own_strategy() {
+    let mut config = ServerConfig::new(Ipv4Addr::new(192, 168, 1, 1));
+    config.allocation_strategy = AllocationStrategy::LeastRecentlyReleased;
+    config.relay_scopes = vec![
+        "10.30.0.0/24@10.30.0.1=10.30.0.10-10.30.0.200#random".parse().unwrap(),
+        "10.40.0.0/24@10.40.0.1#hash".parse().unwrap(),
+        "10.50.0.0/24".parse().unwrap(),
+    ];
+    let strategies: Vec<AllocationStrategy> = config.ip_pools().unwrap().pools().iter().map(IpPool::strategy).collect();
+    assert_eq!(
+        strategies,
+        vec![
+            AllocationStrategy::LeastRecentlyReleased,
+            AllocationStrategy::Random,
+            AllocationStrategy::HashOfMac,
+            // Sans stratégie propre : celle de DHCP_ALLOCATION
+            AllocationStrategy::LeastRecentlyReleased,
+        ]
+    );
+}
+
+#[test]
+fn unknown_strategy_is_rejected() {
+    assert!("randon".parse::<AllocationStrategy>().is_err());
+    assert!("10.30.0.0/24@10.30.0.1#randon".parse::<RelayScope>().is_err());
+}
@@ -0,0 +1,204 @@
+// Outils partagés par les tests d'intégration : serveur `server_dis` lancé sur
+// la boucle locale, machine à états `DhcpServer` sur une base SQLite en mémoire,
+// et échanges de messages DHCP en UDP.
+#![allow(dead_code)]
+
+use std::io::ErrorKind;
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use dhcp_demo::config::ServerConfig;
+use dhcp_demo::db::{self, SqliteStore};
+use dhcp_demo::message::{DhcpMessage, MessageType, BOOTREPLY, BOOTREQUEST};
+use dhcp_demo::options::DhcpOption;
+use dhcp_demo::relay_agent::RelayAgentInfo;
+use dhcp_demo::server::DhcpServer;
+use dhcp_demo::store::LeaseStore;
+use rusqlite::Connection;
+
+// Délai d'attente d'une réponse ; au-delà, le serveur est considéré silencieux
+pub const REPLY_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Serveur `server_dis` écoutant sur 127.0.0.1, arrêté à la fin du test.
+pub struct TestServer {
+    child: Child,
+    pub addr: SocketAddr,
+}
+
+impl TestServer {
+    /// Lance le serveur avec les variables d'environnement `env` en plus de la
+    /// configuration de test (pas de ping de détection de conflit).
+    pub fn start(env: &[(&str, String)]) -> Self {
+        let addr = free_port(Ipv4Addr::LOCALHOST);
+        let child = Command::new(env!("CARGO_BIN_EXE_server_dis"))
+            .arg(addr.to_string())
+            .env("DHCP_PING_TIMEOUT_MS", "0")
+            .envs(env.iter().map(|(name, value)| (*name, value.as_str())))
+            // stdin reste ouvert : le menu d'administration attend sans consommer de CPU
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("lancement de server_dis");
+        TestServer { child, addr }
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Relais `relay` écoutant sur `addr` et retransmettant vers `servers`, arrêté à la fin du test.
+pub struct TestRelay {
+    child: Child,
+    pub addr: SocketAddr,
+}
+
+impl TestRelay {
+    pub fn start(addr: SocketAddr, servers: &[SocketAddr], env: &[(&str, String)]) -> Self {
+        let child = Command::new(env!("CARGO_BIN_EXE_relay"))
+            .arg(addr.to_string())
+            .args(servers.iter().map(SocketAddr::to_string))
+            .envs(env.iter().map(|(name, value)| (*name, value.as_str())))
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("lancement de relay");
+        TestRelay { child, addr }
+    }
+}
+
+impl Drop for TestRelay {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Adresse `ip:port` libre au moment de l'appel.
+pub fn free_port(ip: Ipv4Addr) -> SocketAddr {
+    UdpSocket::bind((ip, 0)).unwrap().local_addr().unwrap()
+}
+
+/// Socket UDP sur `ip` (port éphémère) avec le délai de réponse des tests.
+pub fn bind(ip: Ipv4Addr) -> UdpSocket {
+    let socket = UdpSocket::bind((ip, 0)).unwrap();
+    socket.set_read_timeout(Some(REPLY_TIMEOUT)).unwrap();
+    socket
+}
+
+/// Requête client (BOOTREQUEST) de type `msg_type` pour le dernier octet de MAC `host`.
+pub fn request(msg_type: MessageType, xid: u32, host: u8) -> DhcpMessage {
+    DhcpMessage::new(BOOTREQUEST, msg_type, xid, [0x02, 0, 0, 0, 0, host])
+}
+
+/// Requête du client `host` retransmise par le relais `giaddr` (un saut).
+pub fn relayed(msg_type: MessageType, xid: u32, host: u8, giaddr: Ipv4Addr) -> DhcpMessage {
+    let mut msg = request(msg_type, xid, host);
+    msg.giaddr = giaddr;
+    msg.hops = 1;
+    msg
+}
+
+/// Requête relayée par 127.0.0.1 avec l'option 82 `suboptions`.
+pub fn relayed_with_agent_info(msg_type: MessageType, xid: u32, host: u8, suboptions: &[(u8, &[u8])]) -> DhcpMessage {
+    let mut msg = relayed(msg_type, xid, host, Ipv4Addr::LOCALHOST);
+    let suboptions = suboptions.iter().map(|(code, data)| (*code, data.to_vec())).collect();
+    msg.set_option(DhcpOption::RelayAgentInformation(RelayAgentInfo::new(suboptions)));
+    msg
+}
+
+/// Réponse du serveur à la transaction `xid`, reçue sur `socket`, ou `None` après le délai.
+pub fn recv_reply(socket: &UdpSocket, xid: u32) -> Option<(DhcpMessage, SocketAddr)> {
+    let mut buf = [0u8; 1500];
+    loop {
+        match socket.recv_from(&mut buf) {
+            Ok((len, from)) => match DhcpMessage::parse(&buf[..len]) {
+                Ok(msg) if msg.op == BOOTREPLY && msg.xid == xid => return Some((msg, from)),
+                _ => continue,
+            },
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => return None,
+            Err(e) => panic!("réception : {}", e),
+        }
+    }
+}
+
+/// Envoie `msg` au serveur et attend sa réponse, en renvoyant la requête
+/// tant que le serveur, à peine lancé, n'écoute pas encore.
+pub fn exchange(socket: &UdpSocket, server: SocketAddr, msg: &DhcpMessage) -> Option<(DhcpMessage, SocketAddr)> {
+    for _ in 0..10 {
+        socket.send_to(&msg.to_bytes(), server).unwrap();
+        if let Some(reply) = recv_reply(socket, msg.xid) {
+            return Some(reply);
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    None
+}
+
+/// Plage dynamique des serveurs en mémoire : 127.0.0.100 à 127.0.0.110.
+pub const POOL_START: Ipv4Addr = Ipv4Addr::new(127, 0, 0, 100);
+pub const POOL_END: Ipv4Addr = Ipv4Addr::new(127, 0, 0, 110);
+
+/// Configuration des serveurs en mémoire : 127.0.0.0/24, identifiant 127.0.0.1.
+pub fn memory_config() -> ServerConfig {
+    let mut config = ServerConfig::new(Ipv4Addr::LOCALHOST);
+    config.pool_ranges = vec![(POOL_START, POOL_END)];
+    config.ping_timeout_ms = 0;
+    config
+}
+
+/// Serveur en mémoire sur une base SQLite vierge.
+pub fn memory_server(config: ServerConfig) -> DhcpServer<SqliteStore> {
+    let conn = Connection::open_in_memory().unwrap();
+    db::init_db(&conn).unwrap();
+    let pool = config.ip_pools().unwrap();
+    let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    DhcpServer::new(socket, pool, config, SqliteStore::new(conn))
+}
+
+/// Serveur en mémoire dont les réponses relayées reviennent au port de `client`,
+/// qui joue alors le relais.
+pub fn relayed_server(client: &UdpSocket) -> DhcpServer<SqliteStore> {
+    let mut config = memory_config();
+    config.relay_port = client.local_addr().unwrap().port();
+    memory_server(config)
+}
+
+/// Remet `msg` au serveur comme s'il venait de `client` et renvoie sa réponse.
+pub fn handle<S: LeaseStore>(server: &mut DhcpServer<S>, client: &UdpSocket, msg: &DhcpMessage) -> Option<DhcpMessage> {
+    server.handle_message(&msg.to_bytes(), client.local_addr().unwrap());
+    recv_reply(client, msg.xid).map(|(reply, _)| reply)
+}
+
+/// DISCOVER puis REQUEST (SELECTING) pour le client `host` ; renvoie l'adresse accordée.
+pub fn obtain_lease<S: LeaseStore>(server: &mut DhcpServer<S>, client: &UdpSocket, xid: u32, host: u8) -> Ipv4Addr {
+    obtain_lease_for(server, client, &request(MessageType::Discover, xid, host))
+}
+
+/// Bail obtenu à partir de `discover` : la REQUEST reprend ses champs et ses
+/// options (identifiant client, option 82...) avec l'adresse offerte.
+pub fn obtain_lease_for<S: LeaseStore>(server: &mut DhcpServer<S>, client: &UdpSocket, discover: &DhcpMessage) -> Ipv4Addr {
+    let offer = handle(server, client, discover).expect("OFFER");
+    assert_eq!(offer.message_type(), Some(MessageType::Offer));
+    let mut req = discover.clone();
+    req.set_option(DhcpOption::MessageType(MessageType::Request));
+    req.set_option(DhcpOption::RequestedIpAddress(offer.yiaddr));
+    req.set_option(DhcpOption::ServerIdentifier(server.config.server_ip));
+    let ack = handle(server, client, &req).expect("ACK");
+    assert_eq!(ack.message_type(), Some(MessageType::Ack));
+    ack.yiaddr
+}
+
+/// RELEASE du bail `ip` du client `host`.
+pub fn release<S: LeaseStore>(server: &mut DhcpServer<S>, client: &UdpSocket, xid: u32, host: u8, ip: Ipv4Addr) {
+    let mut msg = request(MessageType::Release, xid, host);
+    msg.ciaddr = ip;
+    msg.set_option(DhcpOption::ServerIdentifier(server.config.server_ip));
+    server.handle_message(&msg.to_bytes(), client.local_addr().unwrap());
+}
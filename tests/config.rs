@@ -0,0 +1,128 @@
+// Lecture de la configuration : une plage ou une variable illisible empêche
+// le démarrage au lieu d'élargir le pool ou de retomber sur une valeur par défaut.
+use std::net::Ipv4Addr;
+use std::process::{Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use dhcp_demo::config::ServerConfig;
+use dhcp_demo::ip_pool::{parse_ranges, PoolError};
+use dhcp_demo::options::{self, DhcpOption};
+
+// Code de sortie de `server_dis` lancé avec `env`, ou `None` s'il tourne encore après 2 s
+fn startup_status(env: &[(&str, &str)]) -> Option<ExitStatus> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_server_dis"))
+        .arg("127.0.0.1:0")
+        .env("DHCP_PING_TIMEOUT_MS", "0")
+        .envs(env.iter().copied())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("lancement de server_dis");
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while Instant::now() < deadline {
+        if let Some(status) = child.try_wait().unwrap() {
+            return Some(status);
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+    None
+}
+
+#[test]
+fn ranges_are_parsed_in_order() {
+    let ranges = parse_ranges("192.168.1.100-192.168.1.150, 192.168.1.200").unwrap();
+    assert_eq!(
+        ranges,
+        vec![
+            (Ipv4Addr::new(192, 168, 1, 100), Ipv4Addr::new(192, 168, 1, 150)),
+            (Ipv4Addr::new(192, 168, 1, 200), Ipv4Addr::new(192, 168, 1, 200)),
+        ]
+    );
+}
+
+#[test]
+fn unreadable_range_is_an_error() {
+    assert_eq!(
+        parse_ranges("192.168.1.10-192.168.1.20,192.168.1.100-192.168.1.300"),
+        Err(PoolError::InvalidRange("192.168.1.100-192.168.1.300".to_string()))
+    );
+}
+
+#[test]
+fn invalid_pool_range_prevents_startup() {
+    let status = startup_status(&[("DHCP_RANGES", "192.168.1.100-192.168.1.300")]).expect("arrêt du serveur");
+    assert!(!status.success());
+}
+
+#[test]
+fn empty_pool_range_list_prevents_startup() {
+    let status = startup_status(&[("DHCP_RANGES", ",")]).expect("arrêt du serveur");
+    assert!(!status.success());
+}
+
+#[test]
+fn invalid_exclusion_prevents_startup() {
+    let status = startup_status(&[("DHCP_EXCLUDE", "127.0.0.150-127.0.0.16O")]).expect("arrêt du serveur");
+    assert!(!status.success());
+}
+
+#[test]
+fn valid_exclusions_start_the_server() {
+    assert!(startup_status(&[("DHCP_EXCLUDE", "127.0.0.150-127.0.0.160,127.0.0.200")]).is_none());
+}
+
+#[test]
+fn invalid_subnet_prevents_startup() {
+    let status = startup_status(&[("DHCP_SUBNET", "127.0.0.0/33")]).expect("arrêt du serveur");
+    assert!(!status.success());
+    let status = startup_status(&[("DHCP_SUBNET_MASK", "255.0.255.0")]).expect("arrêt du serveur");
+    assert!(!status.success());
+}
+
+#[test]
+fn unparsable_variable_prevents_startup() {
+    let status = startup_status(&[("DHCP_RELAY_PORT", "67a")]).expect("arrêt du serveur");
+    assert!(!status.success());
+}
+
+#[test]
+fn zero_lease_time_prevents_startup() {
+    let status = startup_status(&[("DHCP_LEASE_TIME", "0")]).expect("arrêt du serveur");
+    assert!(!status.success());
+}
+
+#[test]
+fn unknown_allocation_strategy_prevents_startup() {
+    let status = startup_status(&[("DHCP_ALLOCATION", "randon")]).expect("arrêt du serveur");
+    assert!(!status.success());
+}
+
+#[test]
+fn invalid_relay_scope_prevents_startup() {
+    for scope in ["127.0.1.0/24@127.0.1.1=127.0.1.100-127.0.1.300", "127.0.1.0/24=", "127.0.1.0/24#randon"] {
+        let status = startup_status(&[("DHCP_RELAY_SCOPES", scope)]).expect("arrêt du serveur");
+        assert!(!status.success(), "{}", scope);
+    }
+}
+
+#[test]
+fn invalid_dns_server_prevents_startup() {
+    let status = startup_status(&[("DHCP_DNS", "1.1.1.1,8.8.8.x")]).expect("arrêt du serveur");
+    assert!(!status.success());
+}
+
+#[test]
+fn dns_servers_are_only_sent_when_configured() {
+    let mut config = ServerConfig::new(Ipv4Addr::new(192, 168, 1, 1));
+    let client = Ipv4Addr::new(192, 168, 1, 100);
+    // Le serveur DHCP n'est pas un résolveur : sans DHCP_DNS, pas d'option 6
+    assert!(config.dns_servers.is_empty());
+    assert!(config.network_options(client, None).iter().all(|opt| opt.code() != options::DOMAIN_NAME_SERVER));
+
+    config.dns_servers = vec![Ipv4Addr::new(1, 1, 1, 1)];
+    assert!(config.network_options(client, None).contains(&DhcpOption::DomainNameServer(vec![Ipv4Addr::new(1, 1, 1, 1)])));
+}
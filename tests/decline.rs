@@ -0,0 +1,118 @@
+// DHCPDECLINE : mise en quarantaine de l'adresse, réservée au détenteur du bail
+// et au serveur désigné par l'option 54.
+mod common;
+
+use std::net::{Ipv4Addr, UdpSocket};
+use std::thread;
+use std::time::Duration;
+
+use common::{bind, handle, memory_config, memory_server, obtain_lease_for, request};
+use dhcp_demo::db::{self, SqliteStore};
+use dhcp_demo::message::{DhcpMessage, MessageType};
+use dhcp_demo::options::DhcpOption;
+use dhcp_demo::server::DhcpServer;
+
+const CLIENT_ID: [u8; 4] = [0, b'c', b'4', b'2'];
+
+fn with_client_id(mut msg: DhcpMessage) -> DhcpMessage {
+    msg.set_option(DhcpOption::ClientIdentifier(CLIENT_ID.to_vec()));
+    msg
+}
+
+fn decline(xid: u32, host: u8, ip: Ipv4Addr, server_id: Ipv4Addr) -> DhcpMessage {
+    let mut msg = with_client_id(request(MessageType::Decline, xid, host));
+    msg.set_option(DhcpOption::RequestedIpAddress(ip));
+    msg.set_option(DhcpOption::ServerIdentifier(server_id));
+    msg
+}
+
+// Prolongation du bail : ACK tant que le serveur connaît encore le bail
+fn renew(server: &mut DhcpServer<SqliteStore>, client: &UdpSocket, xid: u32, host: u8, ip: Ipv4Addr) -> Option<DhcpMessage> {
+    let mut req = with_client_id(request(MessageType::Request, xid, host));
+    req.ciaddr = ip;
+    handle(server, client, &req)
+}
+
+// Dernier statut enregistré pour l'adresse `ip`
+fn last_status(server: &DhcpServer<SqliteStore>, ip: Ipv4Addr) -> String {
+    server.store.flush();
+    let conn = server.store.conn.lock().unwrap();
+    conn.query_row(
+        "SELECT status FROM leases WHERE ip = ?1 ORDER BY id DESC LIMIT 1",
+        [ip.to_string()],
+        |row| row.get(0),
+    )
+    .unwrap()
+}
+
+#[test]
+fn decline_quarantines_the_address() {
+    let mut config = memory_config();
+    config.decline_probation = 1;
+    let mut server = memory_server(config);
+    let client = bind(Ipv4Addr::LOCALHOST);
+    let ip = obtain_lease_for(&mut server, &client, &with_client_id(request(MessageType::Discover, 0x1601, 1)));
+
+    assert!(handle(&mut server, &client, &decline(0x1602, 1, ip, Ipv4Addr::LOCALHOST)).is_none());
+    assert_eq!(last_status(&server, ip), "DECLINED");
+
+    // L'adresse refusée n'est plus proposée pendant la quarantaine...
+    let offer = handle(&mut server, &client, &request(MessageType::Discover, 0x1603, 2)).expect("OFFER");
+    assert_ne!(offer.yiaddr, ip);
+    server.expire_quarantine();
+    let offer = handle(&mut server, &client, &request(MessageType::Discover, 0x1604, 3)).expect("OFFER");
+    assert_ne!(offer.yiaddr, ip);
+
+    // ... puis revient dans le pool (allocation séquentielle : la plus basse libre)
+    thread::sleep(Duration::from_millis(1100));
+    server.expire_quarantine();
+    let offer = handle(&mut server, &client, &request(MessageType::Discover, 0x1605, 4)).expect("OFFER");
+    assert_eq!(offer.yiaddr, ip);
+}
+
+#[test]
+fn quarantine_survives_a_restart() {
+    let mut first = memory_server(memory_config());
+    let client = bind(Ipv4Addr::LOCALHOST);
+    let ip = obtain_lease_for(&mut first, &client, &with_client_id(request(MessageType::Discover, 0x1631, 1)));
+    assert!(handle(&mut first, &client, &decline(0x1632, 1, ip, Ipv4Addr::LOCALHOST)).is_none());
+    first.store.flush();
+
+    let config = memory_config();
+    let quarantine = db::load_quarantine(&first.store.conn.lock().unwrap(), config.decline_probation, config.abandon_time).unwrap();
+    assert_eq!(quarantine.len(), 1);
+    assert_eq!(quarantine[0].0, ip);
+    assert!(quarantine[0].1 > 0 && quarantine[0].1 <= config.decline_probation);
+    // Délai écoulé : l'adresse n'est plus en quarantaine
+    assert!(db::load_quarantine(&first.store.conn.lock().unwrap(), 0, 0).unwrap().is_empty());
+
+    let mut second = memory_server(config);
+    second.restore_quarantine(quarantine);
+    let offer = handle(&mut second, &client, &request(MessageType::Discover, 0x1633, 2)).expect("OFFER");
+    assert_ne!(offer.yiaddr, ip);
+}
+
+#[test]
+fn decline_from_another_chaddr_is_ignored() {
+    let mut server = memory_server(memory_config());
+    let client = bind(Ipv4Addr::LOCALHOST);
+    let ip = obtain_lease_for(&mut server, &client, &with_client_id(request(MessageType::Discover, 0x1611, 1)));
+
+    // Même identifiant client, autre adresse matérielle
+    assert!(handle(&mut server, &client, &decline(0x1612, 9, ip, Ipv4Addr::LOCALHOST)).is_none());
+
+    let ack = renew(&mut server, &client, 0x1613, 1, ip).expect("ACK");
+    assert_eq!(ack.message_type(), Some(MessageType::Ack));
+}
+
+#[test]
+fn decline_for_another_server_is_ignored() {
+    let mut server = memory_server(memory_config());
+    let client = bind(Ipv4Addr::LOCALHOST);
+    let ip = obtain_lease_for(&mut server, &client, &with_client_id(request(MessageType::Discover, 0x1621, 1)));
+
+    assert!(handle(&mut server, &client, &decline(0x1622, 1, ip, Ipv4Addr::new(127, 0, 0, 254))).is_none());
+
+    let ack = renew(&mut server, &client, 0x1623, 1, ip).expect("ACK");
+    assert_eq!(ack.message_type(), Some(MessageType::Ack));
+}
@@ -0,0 +1,56 @@
+// Exclusions ajoutées depuis le menu d'administration : plages retirées du pool
+// dynamique, conservées dans la base et rendues au pool à leur suppression.
+mod common;
+
+use std::net::Ipv4Addr;
+
+use common::{bind, handle, memory_config, memory_server, request, POOL_START};
+use dhcp_demo::db;
+use dhcp_demo::message::MessageType;
+
+#[test]
+fn excluded_range_is_skipped_and_persisted() {
+    let mut first = memory_server(memory_config());
+    let client = bind(Ipv4Addr::LOCALHOST);
+    first.ajouter_exclusion("127.0.0.100-127.0.0.104");
+
+    let offer = handle(&mut first, &client, &request(MessageType::Discover, 0x1401, 1)).expect("OFFER");
+    assert_eq!(offer.yiaddr, Ipv4Addr::new(127, 0, 0, 105));
+    first.store.flush();
+
+    let exclusions = db::load_exclusions(&first.store.conn.lock().unwrap()).unwrap();
+    assert_eq!(exclusions, vec![(POOL_START, Ipv4Addr::new(127, 0, 0, 104))]);
+
+    let mut second = memory_server(memory_config());
+    second.restore_exclusions(exclusions);
+    let offer = handle(&mut second, &client, &request(MessageType::Discover, 0x1402, 1)).expect("OFFER");
+    assert_eq!(offer.yiaddr, Ipv4Addr::new(127, 0, 0, 105));
+}
+
+#[test]
+fn removed_exclusion_returns_addresses_to_the_pool() {
+    let mut config = memory_config();
+    config.exclusions = vec![(POOL_START, Ipv4Addr::new(127, 0, 0, 101))];
+    let mut server = memory_server(config);
+    let client = bind(Ipv4Addr::LOCALHOST);
+    server.ajouter_exclusion("127.0.0.100-127.0.0.103");
+    server.supprimer_exclusion("127.0.0.100-127.0.0.103");
+    server.store.flush();
+    assert!(db::load_exclusions(&server.store.conn.lock().unwrap()).unwrap().is_empty());
+
+    // Les adresses exclues par la configuration restent hors du pool
+    let offer = handle(&mut server, &client, &request(MessageType::Discover, 0x1411, 1)).expect("OFFER");
+    assert_eq!(offer.yiaddr, Ipv4Addr::new(127, 0, 0, 102));
+}
+
+#[test]
+fn configured_exclusions_cannot_be_removed() {
+    let mut config = memory_config();
+    config.exclusions = vec![(POOL_START, Ipv4Addr::new(127, 0, 0, 101))];
+    let mut server = memory_server(config);
+    let client = bind(Ipv4Addr::LOCALHOST);
+    server.supprimer_exclusion("127.0.0.100-127.0.0.101");
+
+    let offer = handle(&mut server, &client, &request(MessageType::Discover, 0x1421, 1)).expect("OFFER");
+    assert_eq!(offer.yiaddr, Ipv4Addr::new(127, 0, 0, 102));
+}
@@ -0,0 +1,60 @@
+// DHCPINFORM : paramètres réseau pour un client déjà configuré, sans bail (RFC 2131, 3.4).
+mod common;
+
+use std::net::{Ipv4Addr, SocketAddr};
+
+use common::{bind, handle, memory_config, memory_server, recv_reply, request};
+use dhcp_demo::message::{MessageType, ReplyDestination, CLIENT_PORT, FLAG_BROADCAST};
+use dhcp_demo::options::{self, DhcpOption};
+
+// Adresse configurée à la main par le client, hors de la plage dynamique
+const CIADDR: Ipv4Addr = Ipv4Addr::new(127, 0, 0, 50);
+
+#[test]
+fn inform_is_acknowledged_without_a_lease() {
+    let mut server = memory_server(memory_config());
+    // Le client émet depuis son adresse : la réponse doit y revenir
+    let client = bind(CIADDR);
+    let available = server.pool.available();
+
+    let mut inform = request(MessageType::Inform, 0x1901, 1);
+    inform.ciaddr = CIADDR;
+    inform.set_option(DhcpOption::ParameterRequestList(vec![
+        options::SUBNET_MASK,
+        options::ROUTER,
+        options::IP_ADDRESS_LEASE_TIME,
+    ]));
+    server.handle_message(&inform.to_bytes(), client.local_addr().unwrap());
+    let (ack, _) = recv_reply(&client, inform.xid).expect("ACK");
+
+    assert_eq!(ack.message_type(), Some(MessageType::Ack));
+    assert_eq!(ack.yiaddr, Ipv4Addr::UNSPECIFIED);
+    assert_eq!(ack.ciaddr, CIADDR);
+    assert_eq!(ack.flags & FLAG_BROADCAST, 0);
+    // Depuis le port client, l'ACK part en unicast vers ciaddr
+    assert_eq!(
+        ack.reply_destination(SocketAddr::new(CIADDR.into(), CLIENT_PORT), server.config.relay_port),
+        ReplyDestination::Ip(SocketAddr::new(CIADDR.into(), CLIENT_PORT))
+    );
+
+    // Identifiant serveur, mais ni durée de bail ni T1/T2
+    assert_eq!(ack.server_identifier(), Some(Ipv4Addr::LOCALHOST));
+    assert!(ack.option(options::IP_ADDRESS_LEASE_TIME).is_none());
+    assert!(ack.option(options::RENEWAL_TIME).is_none());
+    assert!(ack.option(options::REBINDING_TIME).is_none());
+    assert!(ack.options.contains(&DhcpOption::SubnetMask(Ipv4Addr::new(255, 255, 255, 0))));
+
+    // Ni adresse attribuée, ni bail enregistré
+    assert_eq!(server.pool.available(), available);
+    assert!(server.leases.is_empty());
+    server.store.flush();
+    let rows: i64 = server.store.conn.lock().unwrap().query_row("SELECT COUNT(*) FROM leases", [], |row| row.get(0)).unwrap();
+    assert_eq!(rows, 0);
+
+    // Un second INFORM ne change rien non plus
+    let mut again = request(MessageType::Inform, 0x1902, 1);
+    again.ciaddr = CIADDR;
+    assert!(handle(&mut server, &client, &again).is_some());
+    assert_eq!(server.pool.available(), available);
+    assert!(server.leases.is_empty());
+}
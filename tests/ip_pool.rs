@@ -0,0 +1,138 @@
+// Pool d'adresses en bitmap, comparé à un ensemble d'adresses tenu à part.
+use std::collections::BTreeSet;
+use std::net::Ipv4Addr;
+
+use dhcp_demo::ip_pool::{IpPool, PoolError, Subnet};
+
+// Modèle de référence : adresses du pool, exclues et libres
+struct Reference {
+    members: BTreeSet<u32>,
+    excluded: BTreeSet<u32>,
+    free: BTreeSet<u32>,
+}
+
+impl Reference {
+    fn new(members: impl IntoIterator<Item = u32>) -> Self {
+        let members: BTreeSet<u32> = members.into_iter().collect();
+        Reference { free: members.clone(), members, excluded: BTreeSet::new() }
+    }
+
+    fn contains(&self, ip: u32) -> bool {
+        self.members.contains(&ip) && !self.excluded.contains(&ip)
+    }
+}
+
+// xorshift64 à graine fixe : séquence d'opérations reproductible
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+fn ip(addr: u32) -> Ipv4Addr {
+    Ipv4Addr::from(addr)
+}
+
+#[test]
+fn bitmap_matches_a_reference_set() {
+    // /18 : 16382 adresses, soit quatre mots de résumé
+    let subnet: Subnet = "10.20.0.0/18".parse().unwrap();
+    let server = Ipv4Addr::new(10, 20, 16, 1);
+    let mut pool = IpPool::new(subnet, &[], &[server]).unwrap();
+    let (first, last) = subnet.host_range();
+    let (first, last) = (u32::from(first), u32::from(last));
+    let mut reference = Reference::new((first..=last).filter(|&a| a != u32::from(server)));
+    assert_eq!(pool.available(), 16381);
+
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    // Adresses tirées un peu au-delà du sous-réseau pour couvrir les bords
+    let pick = |rng: &mut Rng| first - 2 + (rng.next() % (last - first + 5) as u64) as u32;
+    for _ in 0..50_000 {
+        let addr = pick(&mut rng);
+        match rng.next() % 6 {
+            0 | 1 => {
+                let expected = reference.free.first().copied();
+                assert_eq!(pool.lease_ip(), expected.map(ip));
+                if let Some(addr) = expected {
+                    reference.free.remove(&addr);
+                }
+            }
+            2 => assert_eq!(pool.take_ip(ip(addr)), reference.free.remove(&addr)),
+            3 => {
+                pool.release_ip(&ip(addr));
+                if reference.contains(addr) {
+                    reference.free.insert(addr);
+                }
+            }
+            4 => {
+                pool.exclude(ip(addr));
+                if reference.contains(addr) {
+                    reference.free.remove(&addr);
+                    reference.excluded.insert(addr);
+                }
+            }
+            _ => {
+                pool.include(ip(addr));
+                if reference.excluded.remove(&addr) {
+                    reference.free.insert(addr);
+                }
+            }
+        }
+        assert_eq!(pool.available(), reference.free.len());
+        assert_eq!(pool.contains(ip(addr)), reference.contains(addr), "{}", ip(addr));
+    }
+}
+
+#[test]
+fn allocation_crosses_summary_words() {
+    // Deux plages séparées de plus de 4096 adresses : la seconde n'est
+    // trouvée qu'en passant au mot de résumé suivant
+    let subnet: Subnet = "10.0.0.0/16".parse().unwrap();
+    let ranges = [
+        (Ipv4Addr::new(10, 0, 0, 10), Ipv4Addr::new(10, 0, 0, 11)),
+        (Ipv4Addr::new(10, 0, 200, 0), Ipv4Addr::new(10, 0, 200, 1)),
+    ];
+    let mut pool = IpPool::new(subnet, &ranges, &[]).unwrap();
+    let leased: Vec<_> = std::iter::from_fn(|| pool.lease_ip()).collect();
+    assert_eq!(
+        leased,
+        vec![
+            Ipv4Addr::new(10, 0, 0, 10),
+            Ipv4Addr::new(10, 0, 0, 11),
+            Ipv4Addr::new(10, 0, 200, 0),
+            Ipv4Addr::new(10, 0, 200, 1),
+        ]
+    );
+    assert!(!pool.contains(Ipv4Addr::new(10, 0, 100, 0)));
+
+    // Une adresse rendue dans la seconde plage est retrouvée depuis le début
+    pool.release_ip(&Ipv4Addr::new(10, 0, 200, 1));
+    assert_eq!(pool.lease_ip(), Some(Ipv4Addr::new(10, 0, 200, 1)));
+}
+
+#[test]
+fn network_and_broadcast_are_never_leased() {
+    let subnet: Subnet = "192.168.1.0/30".parse().unwrap();
+    let mut pool = IpPool::new(subnet, &[], &[]).unwrap();
+    assert_eq!(pool.lease_ip(), Some(Ipv4Addr::new(192, 168, 1, 1)));
+    assert_eq!(pool.lease_ip(), Some(Ipv4Addr::new(192, 168, 1, 2)));
+    assert_eq!(pool.lease_ip(), None);
+
+    // /31 : les deux adresses sont attribuables (RFC 3021)
+    let subnet: Subnet = "192.168.1.0/31".parse().unwrap();
+    assert_eq!(IpPool::new(subnet, &[], &[]).unwrap().available(), 2);
+}
+
+#[test]
+fn invalid_ranges_are_rejected() {
+    let subnet: Subnet = "192.168.1.0/24".parse().unwrap();
+    let inverted = (Ipv4Addr::new(192, 168, 1, 20), Ipv4Addr::new(192, 168, 1, 10));
+    assert_eq!(IpPool::new(subnet, &[inverted], &[]).err(), Some(PoolError::InvertedRange(inverted.0, inverted.1)));
+    let outside = (Ipv4Addr::new(192, 168, 1, 200), Ipv4Addr::new(192, 168, 2, 10));
+    assert_eq!(IpPool::new(subnet, &[outside], &[]).err(), Some(PoolError::RangeOutsideSubnet(outside.0, outside.1)));
+}
@@ -0,0 +1,91 @@
+// Machine à états des baux : transitions permises et statuts enregistrés.
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::{Duration, Instant};
+
+use chrono::{TimeDelta, Utc};
+use dhcp_demo::lease::{InvalidTransition, Lease, LeaseState, OFFER_EXPIRED, OFFER_WITHDRAWN, RELEASED_BY_ADMIN};
+
+const ALL_STATES: [LeaseState; 7] = [
+    LeaseState::Offered,
+    LeaseState::Bound,
+    LeaseState::Renewing,
+    LeaseState::Released,
+    LeaseState::Expired,
+    LeaseState::Declined,
+    LeaseState::Abandoned,
+];
+
+fn offer(hold: Duration) -> Lease {
+    let addr = SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), 68);
+    Lease::offer("020000000001", "020000000001", Ipv4Addr::new(192, 168, 1, 100), addr, 1, hold)
+}
+
+#[test]
+fn transitions_follow_the_lease_lifecycle() {
+    use LeaseState::*;
+    let allowed = [
+        (Offered, Offered), (Offered, Bound), (Offered, Released), (Offered, Expired), (Offered, Declined), (Offered, Abandoned),
+        (Bound, Bound), (Bound, Renewing), (Bound, Released), (Bound, Expired), (Bound, Declined), (Bound, Abandoned),
+        (Renewing, Bound), (Renewing, Released), (Renewing, Expired), (Renewing, Declined), (Renewing, Abandoned),
+    ];
+    for from in ALL_STATES {
+        for to in ALL_STATES {
+            assert_eq!(from.can_transition_to(to), allowed.contains(&(from, to)), "{} -> {}", from, to);
+        }
+    }
+}
+
+#[test]
+fn states_round_trip_through_their_database_label() {
+    for state in ALL_STATES {
+        assert_eq!(state.as_str().parse::<LeaseState>(), Ok(state));
+    }
+    assert_eq!(LeaseState::Bound.as_str(), "ACKNOWLEDGED");
+    assert!("BOUND".parse::<LeaseState>().is_err());
+}
+
+#[test]
+fn bind_confirms_the_offer() {
+    let mut lease = offer(Duration::from_secs(60));
+    assert_eq!(lease.bind(3600), Ok("ACKNOWLEDGED"));
+    assert_eq!(lease.state, LeaseState::Bound);
+    assert_eq!(lease.offer_deadline, None);
+    assert_eq!(lease.lease_time, 3600);
+    let remaining = lease.end_time.unwrap() - Utc::now();
+    assert!(remaining > TimeDelta::seconds(3590) && remaining <= TimeDelta::seconds(3600));
+}
+
+#[test]
+fn renew_extends_a_bound_lease_only() {
+    let mut lease = offer(Duration::from_secs(60));
+    assert_eq!(lease.renew(600), Err(InvalidTransition { from: LeaseState::Offered, to: LeaseState::Renewing }));
+
+    lease.bind(60).unwrap();
+    assert_eq!(lease.renew(7200), Ok("ACKNOWLEDGED"));
+    assert_eq!(lease.state, LeaseState::Bound);
+    assert_eq!(lease.lease_time, 7200);
+    assert!(lease.end_time.unwrap() - Utc::now() > TimeDelta::seconds(7190));
+
+    // Un bail terminé ne se prolonge plus
+    lease.transition(LeaseState::Released).unwrap();
+    assert!(lease.renew(600).is_err());
+    assert!(lease.bind(600).is_err());
+}
+
+#[test]
+fn final_statuses_distinguish_offers_from_leases() {
+    let mut expired_offer = offer(Duration::ZERO);
+    assert!(expired_offer.offer_expired(Instant::now()));
+    assert_eq!(expired_offer.transition(LeaseState::Expired), Ok(OFFER_EXPIRED));
+
+    let mut withdrawn = offer(Duration::from_secs(60));
+    assert!(!withdrawn.offer_expired(Instant::now()));
+    assert_eq!(withdrawn.withdraw_offer(), Ok(OFFER_WITHDRAWN));
+
+    let mut bound = offer(Duration::from_secs(60));
+    bound.bind(0).unwrap();
+    assert!(bound.lease_expired(Utc::now()));
+    assert!(bound.withdraw_offer().is_err());
+    assert_eq!(bound.release_by_admin(), Ok(RELEASED_BY_ADMIN));
+    assert_eq!(bound.state, LeaseState::Released);
+}
@@ -0,0 +1,114 @@
+// Cycle de vie des baux dans le serveur : offres répétées, offres abandonnées
+// et baux arrivés à échéance.
+mod common;
+
+use std::net::Ipv4Addr;
+use std::thread;
+use std::time::Duration;
+
+use common::{bind, handle, memory_config, memory_server, obtain_lease_for, request};
+use dhcp_demo::db::SqliteStore;
+use dhcp_demo::lease::{LeaseState, OFFER_EXPIRED};
+use dhcp_demo::message::MessageType;
+use dhcp_demo::options::DhcpOption;
+use dhcp_demo::server::DhcpServer;
+
+// Nombre de lignes de l'historique au statut `status`
+fn count(server: &DhcpServer<SqliteStore>, status: &str) -> i64 {
+    server.store.flush();
+    let conn = server.store.conn.lock().unwrap();
+    conn.query_row("SELECT COUNT(*) FROM leases WHERE status = ?1", [status], |row| row.get(0)).unwrap()
+}
+
+#[test]
+fn repeated_discover_reuses_the_pending_offer() {
+    let mut config = memory_config();
+    config.offer_hold_time = 1;
+    let mut server = memory_server(config);
+    let client = bind(Ipv4Addr::LOCALHOST);
+    let available = server.pool.available();
+
+    let first = handle(&mut server, &client, &request(MessageType::Discover, 0x0401, 1)).expect("OFFER");
+    assert_eq!(server.pool.available(), available - 1);
+
+    // Retransmission (même xid) puis nouvelle transaction : même adresse, rien de plus pris au pool
+    for xid in [0x0401, 0x0402] {
+        let again = handle(&mut server, &client, &request(MessageType::Discover, xid, 1)).expect("OFFER");
+        assert_eq!(again.yiaddr, first.yiaddr);
+        assert_eq!(server.pool.available(), available - 1);
+    }
+    assert_eq!(server.leases.len(), 1);
+    assert_eq!(server.leases["020000000001"].state, LeaseState::Offered);
+    // Une seule offre enregistrée
+    assert_eq!(count(&server, "OFFERED"), 1);
+}
+
+#[test]
+fn unanswered_offer_expires_and_frees_the_address() {
+    let mut config = memory_config();
+    config.offer_hold_time = 1;
+    let mut server = memory_server(config);
+    let client = bind(Ipv4Addr::LOCALHOST);
+    let available = server.pool.available();
+    let offer = handle(&mut server, &client, &request(MessageType::Discover, 0x0501, 1)).expect("OFFER");
+
+    // Délai de réservation pas encore écoulé : l'offre tient
+    server.expire_offers();
+    assert_eq!(server.leases.len(), 1);
+    assert_eq!(server.pool.available(), available - 1);
+
+    thread::sleep(Duration::from_millis(1100));
+    server.expire_offers();
+    assert!(server.leases.is_empty());
+    assert_eq!(server.pool.available(), available);
+    assert_eq!(count(&server, "OFFERED"), 0);
+    assert_eq!(count(&server, OFFER_EXPIRED), 1);
+
+    // L'adresse est de nouveau proposée, ici à un autre client
+    let next = handle(&mut server, &client, &request(MessageType::Discover, 0x0502, 2)).expect("OFFER");
+    assert_eq!(next.yiaddr, offer.yiaddr);
+}
+
+#[test]
+fn bound_lease_expires_after_its_lease_time() {
+    let mut config = memory_config();
+    config.lease_time = 1;
+    let mut server = memory_server(config);
+    let client = bind(Ipv4Addr::LOCALHOST);
+    let available = server.pool.available();
+
+    // Le client demande une heure : la durée configurée l'emporte
+    let mut discover = request(MessageType::Discover, 0x0601, 1);
+    discover.set_option(DhcpOption::IpAddressLeaseTime(3600));
+    let offer = handle(&mut server, &client, &discover).expect("OFFER");
+    assert_eq!(offer.lease_time(), Some(1));
+    let ip = obtain_lease_for(&mut server, &client, &discover);
+    assert_eq!(server.leases["020000000001"].lease_time, 1);
+
+    server.expire_leases();
+    assert_eq!(server.leases["020000000001"].state, LeaseState::Bound);
+
+    thread::sleep(Duration::from_millis(1100));
+    server.expire_leases();
+    assert!(server.leases.is_empty());
+    assert_eq!(server.pool.available(), available);
+    assert_eq!(count(&server, "ACKNOWLEDGED"), 0);
+    assert_eq!(count(&server, "EXPIRED"), 1);
+
+    let next = handle(&mut server, &client, &request(MessageType::Discover, 0x0602, 2)).expect("OFFER");
+    assert_eq!(next.yiaddr, ip);
+}
+
+#[test]
+fn granted_lease_time_is_clamped() {
+    let mut config = memory_config();
+    config.lease_time = 600;
+    assert_eq!(config.grant_lease_time(None), 600);
+    assert_eq!(config.grant_lease_time(Some(60)), 60);
+    assert_eq!(config.grant_lease_time(Some(3600)), 600);
+    // Une durée nulle expirerait aussitôt : au moins une seconde
+    assert_eq!(config.grant_lease_time(Some(0)), 1);
+    config.lease_time = 0;
+    assert_eq!(config.grant_lease_time(None), 1);
+    assert_eq!(config.grant_lease_time(Some(3600)), 1);
+}
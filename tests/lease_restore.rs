@@ -0,0 +1,114 @@
+// Reprise au démarrage des baux confirmés enregistrés dans la base.
+mod common;
+
+use std::net::Ipv4Addr;
+
+use common::{bind, handle, memory_config, memory_server, obtain_lease, release, request};
+use dhcp_demo::db;
+use dhcp_demo::lease::OFFER_EXPIRED;
+use dhcp_demo::message::MessageType;
+use dhcp_demo::options::DhcpOption;
+
+#[test]
+fn bound_leases_survive_a_restart() {
+    let mut first = memory_server(memory_config());
+    let client = bind(Ipv4Addr::LOCALHOST);
+    let released = obtain_lease(&mut first, &client, 0x0801, 1);
+    let kept = obtain_lease(&mut first, &client, 0x0802, 2);
+    release(&mut first, &client, 0x0803, 1, released);
+    first.store.flush();
+
+    // Seul le bail encore confirmé est rechargé
+    let leases = db::load_active_leases(&first.store.conn.lock().unwrap()).unwrap();
+    assert_eq!(leases.len(), 1);
+    assert_eq!(leases[0].client_id, "020000000002");
+    assert_eq!(leases[0].ip, kept);
+
+    let mut second = memory_server(memory_config());
+    second.restore_leases(leases);
+
+    // Le client retrouve son adresse...
+    let offer = handle(&mut second, &client, &request(MessageType::Discover, 0x0804, 2)).expect("OFFER");
+    assert_eq!(offer.yiaddr, kept);
+
+    // ... qui n'est plus proposée aux autres
+    let first_offer = handle(&mut second, &client, &request(MessageType::Discover, 0x0805, 3)).expect("OFFER");
+    let second_offer = handle(&mut second, &client, &request(MessageType::Discover, 0x0806, 4)).expect("OFFER");
+    assert_ne!(first_offer.yiaddr, kept);
+    assert_ne!(second_offer.yiaddr, kept);
+}
+
+#[test]
+fn init_reboot_is_acknowledged_after_a_restart() {
+    let mut first = memory_server(memory_config());
+    let client = bind(Ipv4Addr::LOCALHOST);
+    let ip = obtain_lease(&mut first, &client, 0x0811, 1);
+    first.store.flush();
+    let leases = db::load_active_leases(&first.store.conn.lock().unwrap()).unwrap();
+
+    let mut second = memory_server(memory_config());
+    second.restore_leases(leases);
+
+    // INIT-REBOOT : adresse demandée, sans identifiant serveur ni ciaddr
+    let mut reboot = request(MessageType::Request, 0x0812, 1);
+    reboot.set_option(DhcpOption::RequestedIpAddress(ip));
+    let ack = handle(&mut second, &client, &reboot).expect("ACK");
+    assert_eq!(ack.message_type(), Some(MessageType::Ack));
+    assert_eq!(ack.yiaddr, ip);
+
+    // Un autre client ne peut pas la réclamer
+    let mut claim = request(MessageType::Request, 0x0813, 2);
+    claim.set_option(DhcpOption::RequestedIpAddress(ip));
+    claim.set_option(DhcpOption::ServerIdentifier(Ipv4Addr::LOCALHOST));
+    let nak = handle(&mut second, &client, &claim).expect("NAK");
+    assert_eq!(nak.message_type(), Some(MessageType::Nak));
+}
+
+#[test]
+fn expired_leases_are_not_restored() {
+    let server = memory_server(memory_config());
+    let conn = server.store.conn.lock().unwrap();
+    conn.execute(
+        "INSERT INTO leases (mac, ip, status, end_time, client_id)
+         VALUES ('020000000001', '127.0.0.100', 'ACKNOWLEDGED', datetime('now', '-1 hour'), '020000000001')",
+        [],
+    )
+    .unwrap();
+    conn.execute(
+        "INSERT INTO leases (mac, ip, status, end_time, client_id)
+         VALUES ('020000000002', '127.0.0.101', 'ACKNOWLEDGED', datetime('now', '+1 hour'), '020000000002')",
+        [],
+    )
+    .unwrap();
+
+    let leases = db::load_active_leases(&conn).unwrap();
+    assert_eq!(leases.len(), 1);
+    assert_eq!(leases[0].ip, Ipv4Addr::new(127, 0, 0, 101));
+}
+
+#[test]
+fn pending_offers_are_closed_at_startup() {
+    let mut server = memory_server(memory_config());
+    let client = bind(Ipv4Addr::LOCALHOST);
+    let offer = handle(&mut server, &client, &request(MessageType::Discover, 0x0821, 1)).expect("OFFER");
+    let kept = obtain_lease(&mut server, &client, 0x0822, 2);
+    server.store.flush();
+
+    let conn = server.store.conn.lock().unwrap();
+    assert_eq!(db::close_stale_offers(&conn).unwrap(), 1);
+    let (status, closed): (String, bool) = conn
+        .query_row(
+            "SELECT status, end_time IS NOT NULL FROM leases WHERE ip = ?1",
+            [offer.yiaddr.to_string()],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap();
+    assert_eq!(status, OFFER_EXPIRED);
+    assert!(closed);
+
+    // Le bail confirmé n'est pas touché
+    let leases = db::load_active_leases(&conn).unwrap();
+    assert_eq!(leases.len(), 1);
+    assert_eq!(leases[0].ip, kept);
+    assert_eq!(db::close_stale_offers(&conn).unwrap(), 0);
+}
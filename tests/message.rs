@@ -0,0 +1,104 @@
+// Codec binaire des messages DHCP (RFC 2131, section 2).
+use std::net::Ipv4Addr;
+
+use dhcp_demo::message::{DhcpMessage, MessageError, MessageType, BOOTREPLY, BOOTREQUEST, MAGIC_COOKIE};
+use dhcp_demo::options::{self, DhcpOption};
+
+// Offre dont tous les champs de l'en-tête sont renseignés
+fn offer() -> DhcpMessage {
+    let mut msg = DhcpMessage::new(BOOTREPLY, MessageType::Offer, 0xdeadbeef, [0x02, 0, 0x5e, 0x10, 0, 0x01]);
+    msg.hops = 2;
+    msg.secs = 7;
+    msg.flags = 0x8000;
+    msg.ciaddr = Ipv4Addr::new(10, 0, 0, 1);
+    msg.yiaddr = Ipv4Addr::new(10, 0, 0, 2);
+    msg.siaddr = Ipv4Addr::new(10, 0, 0, 3);
+    msg.giaddr = Ipv4Addr::new(10, 0, 0, 4);
+    msg.sname[..4].copy_from_slice(b"boot");
+    msg.file[..9].copy_from_slice(b"pxelinux0");
+    msg.set_option(DhcpOption::ServerIdentifier(Ipv4Addr::new(10, 0, 0, 3)));
+    msg.set_option(DhcpOption::IpAddressLeaseTime(3600));
+    msg.set_option(DhcpOption::Router(vec![Ipv4Addr::new(10, 0, 0, 254)]));
+    msg
+}
+
+// Longueur de chaque occurrence de l'option `code` dans un message encodé
+fn option_lengths(bytes: &[u8], code: u8) -> Vec<usize> {
+    let mut lengths = Vec::new();
+    let mut i = 240;
+    while i < bytes.len() && bytes[i] != options::END {
+        if bytes[i] == options::PAD {
+            i += 1;
+            continue;
+        }
+        let len = bytes[i + 1] as usize;
+        if bytes[i] == code {
+            lengths.push(len);
+        }
+        i += 2 + len;
+    }
+    lengths
+}
+
+#[test]
+fn message_round_trips() {
+    let msg = offer();
+    let bytes = msg.to_bytes();
+    assert_eq!(DhcpMessage::parse(&bytes), Ok(msg));
+
+    // Champs fixes aux positions de la RFC 2131
+    assert_eq!(bytes[0], BOOTREPLY);
+    assert_eq!(&bytes[4..8], &[0xde, 0xad, 0xbe, 0xef]);
+    assert_eq!(&bytes[16..20], &[10, 0, 0, 2]);
+    assert_eq!(&bytes[28..34], &[0x02, 0, 0x5e, 0x10, 0, 0x01]);
+    assert_eq!(&bytes[236..240], &MAGIC_COOKIE);
+    assert_eq!(&bytes[240..243], &[options::MESSAGE_TYPE, 1, 2]);
+}
+
+#[test]
+fn short_messages_are_padded_to_bootp_size() {
+    let bytes = DhcpMessage::new(BOOTREQUEST, MessageType::Discover, 1, [0x02, 0, 0, 0, 0, 1]).to_bytes();
+    assert_eq!(bytes.len(), 300);
+    // MESSAGE_TYPE, END, puis remplissage
+    assert_eq!(&bytes[240..244], &[options::MESSAGE_TYPE, 1, 1, options::END]);
+    assert!(bytes[244..].iter().all(|&b| b == options::PAD));
+}
+
+#[test]
+fn unknown_options_are_kept() {
+    let mut msg = offer();
+    msg.set_option(DhcpOption::Unknown(43, vec![1, 2, 3]));
+    msg.set_option(DhcpOption::Unknown(224, Vec::new()));
+    let parsed = DhcpMessage::parse(&msg.to_bytes()).unwrap();
+    assert_eq!(parsed.option(43), Some(&DhcpOption::Unknown(43, vec![1, 2, 3])));
+    assert_eq!(parsed.option(224), Some(&DhcpOption::Unknown(224, Vec::new())));
+    assert_eq!(parsed, msg);
+}
+
+#[test]
+fn long_options_are_split_and_concatenated() {
+    // RFC 3396 : 600 octets envoyés en trois occurrences (255, 255, 90)
+    let domain = "a".repeat(600);
+    let mut msg = offer();
+    msg.set_option(DhcpOption::DomainName(domain.clone()));
+    let bytes = msg.to_bytes();
+    assert_eq!(option_lengths(&bytes, options::DOMAIN_NAME), vec![255, 255, 90]);
+
+    let parsed = DhcpMessage::parse(&bytes).unwrap();
+    assert_eq!(parsed.option(options::DOMAIN_NAME), Some(&DhcpOption::DomainName(domain)));
+}
+
+#[test]
+fn invalid_messages_are_rejected() {
+    let bytes = offer().to_bytes();
+    assert_eq!(DhcpMessage::parse(&bytes[..239]), Err(MessageError::TooShort(239)));
+
+    let mut bad_cookie = bytes.clone();
+    bad_cookie[236] = 0;
+    assert_eq!(DhcpMessage::parse(&bad_cookie), Err(MessageError::BadMagicCookie));
+
+    // Option coupée par la fin du datagramme
+    let mut truncated = bytes[..240].to_vec();
+    truncated.extend_from_slice(&[options::HOST_NAME, 8, b'p', b'c']);
+    assert_eq!(DhcpMessage::parse(&truncated), Err(MessageError::TruncatedOption(options::HOST_NAME)));
+}
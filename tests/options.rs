@@ -0,0 +1,102 @@
+// Encodage et décodage des options DHCP (RFC 2132).
+use std::net::Ipv4Addr;
+
+use dhcp_demo::message::{MessageError, MessageType};
+use dhcp_demo::options::{self, decode_options, encode_options, parse_option_list, DhcpOption};
+use dhcp_demo::relay_agent::{RelayAgentInfo, CIRCUIT_ID};
+
+fn encode(options: &[DhcpOption]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    encode_options(options, &mut buf);
+    buf
+}
+
+#[test]
+fn typed_options_round_trip() {
+    let all = vec![
+        DhcpOption::SubnetMask(Ipv4Addr::new(255, 255, 255, 0)),
+        DhcpOption::Router(vec![Ipv4Addr::new(192, 168, 1, 1)]),
+        DhcpOption::DomainNameServer(vec![Ipv4Addr::new(1, 1, 1, 1), Ipv4Addr::new(8, 8, 8, 8)]),
+        DhcpOption::HostName("poste-12".to_string()),
+        DhcpOption::DomainName("lan".to_string()),
+        DhcpOption::RequestedIpAddress(Ipv4Addr::new(192, 168, 1, 100)),
+        DhcpOption::IpAddressLeaseTime(86400),
+        DhcpOption::MessageType(MessageType::Request),
+        DhcpOption::ServerIdentifier(Ipv4Addr::new(192, 168, 1, 1)),
+        DhcpOption::ParameterRequestList(vec![1, 3, 6]),
+        DhcpOption::Message("adresse déjà utilisée".to_string()),
+        DhcpOption::RenewalTime(43200),
+        DhcpOption::RebindingTime(75600),
+        DhcpOption::ClientIdentifier(vec![1, 0x02, 0, 0, 0, 0, 1]),
+        DhcpOption::RelayAgentInformation(RelayAgentInfo::new(vec![(CIRCUIT_ID, b"Gi0/1".to_vec())])),
+    ];
+    assert_eq!(decode_options(&encode(&all)), Ok(all));
+}
+
+#[test]
+fn options_are_encoded_as_tlv() {
+    let buf = encode(&[DhcpOption::IpAddressLeaseTime(3600), DhcpOption::Pad, DhcpOption::HostName(String::new())]);
+    assert_eq!(
+        buf,
+        vec![options::IP_ADDRESS_LEASE_TIME, 4, 0, 0, 0x0e, 0x10, options::PAD, options::HOST_NAME, 0, options::END]
+    );
+}
+
+#[test]
+fn padding_is_skipped_and_end_stops_decoding() {
+    let buf = [options::PAD, options::PAD, options::MESSAGE_TYPE, 1, 3, options::END, options::HOST_NAME, 200];
+    assert_eq!(decode_options(&buf), Ok(vec![DhcpOption::MessageType(MessageType::Request)]));
+    // Sans END, la zone d'options s'arrête à la fin du message
+    assert_eq!(decode_options(&[options::MESSAGE_TYPE, 1, 3]), Ok(vec![DhcpOption::MessageType(MessageType::Request)]));
+}
+
+#[test]
+fn malformed_values_are_kept_as_unknown() {
+    let buf = [
+        options::SUBNET_MASK, 3, 255, 255, 255,
+        options::ROUTER, 5, 10, 0, 0, 1, 9,
+        options::MESSAGE_TYPE, 1, 42,
+        options::CLIENT_IDENTIFIER, 1, 1,
+        options::END,
+    ];
+    assert_eq!(
+        decode_options(&buf),
+        Ok(vec![
+            DhcpOption::Unknown(options::SUBNET_MASK, vec![255, 255, 255]),
+            DhcpOption::Unknown(options::ROUTER, vec![10, 0, 0, 1, 9]),
+            DhcpOption::Unknown(options::MESSAGE_TYPE, vec![42]),
+            DhcpOption::Unknown(options::CLIENT_IDENTIFIER, vec![1]),
+        ])
+    );
+}
+
+#[test]
+fn truncated_options_are_errors() {
+    // Code sans longueur
+    assert_eq!(decode_options(&[options::HOST_NAME]), Err(MessageError::TruncatedOption(options::HOST_NAME)));
+    // Longueur au-delà de la fin du tampon
+    assert_eq!(
+        decode_options(&[options::MESSAGE_TYPE, 1, 1, options::ROUTER, 4, 10, 0]),
+        Err(MessageError::TruncatedOption(options::ROUTER))
+    );
+    // Une option complète suivie de remplissage reste valide
+    assert_eq!(decode_options(&[options::ROUTER, 4, 10, 0, 0, 1, options::PAD]).map(|o| o.len()), Ok(1));
+}
+
+#[test]
+fn option_lists_are_parsed_by_value_kind() {
+    assert_eq!(
+        parse_option_list("3=10.0.0.1; 6=1.1.1.1, 8.8.8.8;15=lab.local;43=0x0104C0a8;;"),
+        Ok(vec![
+            DhcpOption::Router(vec![Ipv4Addr::new(10, 0, 0, 1)]),
+            DhcpOption::DomainNameServer(vec![Ipv4Addr::new(1, 1, 1, 1), Ipv4Addr::new(8, 8, 8, 8)]),
+            DhcpOption::DomainName("lab.local".to_string()),
+            DhcpOption::Unknown(43, vec![0x01, 0x04, 0xc0, 0xa8]),
+        ])
+    );
+    assert_eq!(parse_option_list(""), Ok(Vec::new()));
+    // Entrée fautive renvoyée telle quelle ; options gérées par le serveur refusées
+    assert_eq!(parse_option_list("15=lab;43=0xé1"), Err("43=0xé1".to_string()));
+    assert_eq!(parse_option_list("54=10.0.0.1"), Err("54=10.0.0.1".to_string()));
+    assert_eq!(parse_option_list("255=1"), Err("255=1".to_string()));
+}
@@ -0,0 +1,186 @@
+// Requêtes relayées (giaddr) : sélection du sous-réseau et réponse au relais.
+// Le relais est simulé par un socket UDP sur 127.0.1.1, adresse du relais
+// dans le sous-réseau distant 127.0.1.0/24.
+mod common;
+
+use std::net::{Ipv4Addr, UdpSocket};
+
+use common::{bind, exchange, recv_reply, relayed, request, TestServer, REPLY_TIMEOUT};
+use dhcp_demo::message::{MessageType, FLAG_BROADCAST};
+use dhcp_demo::options::{self, DhcpOption};
+use dhcp_demo::relay_agent::{self, RelayAgentInfo};
+
+const RELAY_IP: Ipv4Addr = Ipv4Addr::new(127, 0, 1, 1);
+// Relais d'un sous-réseau que le serveur ne dessert pas
+const STRANGER_IP: Ipv4Addr = Ipv4Addr::new(127, 0, 9, 1);
+
+// Serveur desservant 127.0.1.0/24 à travers le relais, qui écoute sur `relay`
+fn start_with_relay(relay: &UdpSocket) -> TestServer {
+    TestServer::start(&[
+        ("DHCP_RELAY_SCOPES", "127.0.1.0/24@127.0.1.1=127.0.1.100-127.0.1.110".to_string()),
+        ("DHCP_RELAY_PORT", relay.local_addr().unwrap().port().to_string()),
+    ])
+}
+
+// Relais inconnu écoutant sur le même port que `relay` : il recevrait une éventuelle réponse
+fn stranger_relay(relay: &UdpSocket) -> UdpSocket {
+    let socket = UdpSocket::bind((STRANGER_IP, relay.local_addr().unwrap().port())).unwrap();
+    socket.set_read_timeout(Some(REPLY_TIMEOUT)).unwrap();
+    socket
+}
+
+#[test]
+fn relayed_discover_is_offered_from_the_relay_subnet() {
+    let relay = bind(RELAY_IP);
+    let server = start_with_relay(&relay);
+
+    let discover = relayed(MessageType::Discover, 0x2101, 1, RELAY_IP);
+    let (offer, from) = exchange(&relay, server.addr, &discover).expect("OFFER relayé");
+
+    assert_eq!(from, server.addr);
+    assert_eq!(offer.message_type(), Some(MessageType::Offer));
+    assert_eq!(offer.yiaddr, Ipv4Addr::new(127, 0, 1, 100));
+    assert_eq!(offer.giaddr, RELAY_IP);
+    assert_eq!(offer.hops, 0);
+    assert_eq!(offer.server_identifier(), Some(Ipv4Addr::LOCALHOST));
+    assert!(offer.options.contains(&DhcpOption::SubnetMask(Ipv4Addr::new(255, 255, 255, 0))));
+    assert!(offer.options.contains(&DhcpOption::Router(vec![RELAY_IP])));
+}
+
+#[test]
+fn relayed_request_is_acknowledged_through_the_relay() {
+    let relay = bind(RELAY_IP);
+    let server = start_with_relay(&relay);
+
+    let discover = relayed(MessageType::Discover, 0x2102, 2, RELAY_IP);
+    let (offer, _) = exchange(&relay, server.addr, &discover).expect("OFFER relayé");
+
+    let mut req = relayed(MessageType::Request, 0x2102, 2, RELAY_IP);
+    req.set_option(DhcpOption::RequestedIpAddress(offer.yiaddr));
+    req.set_option(DhcpOption::ServerIdentifier(Ipv4Addr::LOCALHOST));
+    let (ack, _) = exchange(&relay, server.addr, &req).expect("ACK relayé");
+
+    assert_eq!(ack.message_type(), Some(MessageType::Ack));
+    assert_eq!(ack.yiaddr, offer.yiaddr);
+    assert_eq!(ack.giaddr, RELAY_IP);
+}
+
+#[test]
+fn relayed_request_for_another_subnet_is_refused() {
+    let relay = bind(RELAY_IP);
+    let server = start_with_relay(&relay);
+
+    // Adresse du réseau local demandée depuis le site distant
+    let mut req = relayed(MessageType::Request, 0x2103, 3, RELAY_IP);
+    req.set_option(DhcpOption::RequestedIpAddress(Ipv4Addr::new(127, 0, 0, 50)));
+    let (nak, _) = exchange(&relay, server.addr, &req).expect("NAK relayé");
+
+    assert_eq!(nak.message_type(), Some(MessageType::Nak));
+    assert_eq!(nak.giaddr, RELAY_IP);
+    // Requête sans drapeau de diffusion : le serveur le positionne pour le relais
+    assert_eq!(req.flags & FLAG_BROADCAST, 0);
+    assert_eq!(nak.flags & FLAG_BROADCAST, FLAG_BROADCAST);
+}
+
+#[test]
+fn unknown_relay_is_ignored() {
+    let relay = bind(RELAY_IP);
+    let server = start_with_relay(&relay);
+    // Serveur prêt : un relais connu obtient une réponse
+    let known = relayed(MessageType::Discover, 0x2104, 4, RELAY_IP);
+    exchange(&relay, server.addr, &known).expect("OFFER relayé");
+
+    let stranger = stranger_relay(&relay);
+    let discover = relayed(MessageType::Discover, 0x2105, 5, STRANGER_IP);
+    stranger.send_to(&discover.to_bytes(), server.addr).unwrap();
+    assert!(recv_reply(&stranger, discover.xid).is_none());
+}
+
+#[test]
+fn unknown_relay_requests_are_not_refused() {
+    let relay = bind(RELAY_IP);
+    let server = start_with_relay(&relay);
+    let known = relayed(MessageType::Discover, 0x2107, 4, RELAY_IP);
+    exchange(&relay, server.addr, &known).expect("OFFER relayé");
+
+    // INIT-REBOOT et RENEWING d'un réseau desservi par un autre serveur : pas de NAK
+    let stranger = stranger_relay(&relay);
+    let mut reboot = relayed(MessageType::Request, 0x2108, 5, STRANGER_IP);
+    reboot.set_option(DhcpOption::RequestedIpAddress(Ipv4Addr::new(127, 0, 9, 9)));
+    stranger.send_to(&reboot.to_bytes(), server.addr).unwrap();
+    assert!(recv_reply(&stranger, reboot.xid).is_none());
+
+    let mut renew = relayed(MessageType::Request, 0x2109, 5, STRANGER_IP);
+    renew.ciaddr = Ipv4Addr::new(127, 0, 9, 9);
+    stranger.send_to(&renew.to_bytes(), server.addr).unwrap();
+    assert!(recv_reply(&stranger, renew.xid).is_none());
+}
+
+#[test]
+fn direct_discover_stays_on_the_local_subnet() {
+    let relay = bind(RELAY_IP);
+    let server = start_with_relay(&relay);
+    let client = bind(Ipv4Addr::LOCALHOST);
+
+    let discover = request(MessageType::Discover, 0x2106, 6);
+    let (offer, _) = exchange(&client, server.addr, &discover).expect("OFFER direct");
+
+    assert_eq!(offer.message_type(), Some(MessageType::Offer));
+    assert_eq!(offer.yiaddr, Ipv4Addr::new(127, 0, 0, 2));
+    assert!(offer.giaddr.is_unspecified());
+    assert!(offer.options.contains(&DhcpOption::Router(vec![Ipv4Addr::LOCALHOST])));
+}
+
+#[test]
+fn relay_agent_information_is_echoed_verbatim() {
+    let relay = bind(RELAY_IP);
+    let server = start_with_relay(&relay);
+
+    let info = RelayAgentInfo::new(vec![
+        (relay_agent::CIRCUIT_ID, b"Gi0/12".to_vec()),
+        (relay_agent::REMOTE_ID, vec![0x00, 0x1A, 0x2B, 0x3C, 0x4D, 0x5E]),
+        (9, vec![0, 0, 0, 9, 1, 0xFF]),
+    ]);
+    let mut discover = relayed(MessageType::Discover, 0x2201, 7, RELAY_IP);
+    discover.set_option(DhcpOption::RelayAgentInformation(info.clone()));
+    let (offer, _) = exchange(&relay, server.addr, &discover).expect("OFFER relayé");
+
+    assert_eq!(offer.option(options::RELAY_AGENT_INFORMATION), Some(&DhcpOption::RelayAgentInformation(info)));
+}
+
+#[test]
+fn link_selection_overrides_giaddr() {
+    let relay = bind(RELAY_IP);
+    // Le relais (127.0.1.1) dessert aussi 127.0.2.0/24, désigné par la sous-option 5
+    let server = TestServer::start(&[
+        (
+            "DHCP_RELAY_SCOPES",
+            "127.0.1.0/24@127.0.1.1=127.0.1.100-127.0.1.110;127.0.2.0/24@127.0.2.1=127.0.2.100-127.0.2.110".to_string(),
+        ),
+        ("DHCP_RELAY_PORT", relay.local_addr().unwrap().port().to_string()),
+    ]);
+
+    let info = RelayAgentInfo::new(vec![(relay_agent::LINK_SELECTION, vec![127, 0, 2, 0])]);
+    let mut discover = relayed(MessageType::Discover, 0x2202, 8, RELAY_IP);
+    discover.set_option(DhcpOption::RelayAgentInformation(info));
+    let (offer, _) = exchange(&relay, server.addr, &discover).expect("OFFER relayé");
+
+    assert_eq!(offer.yiaddr, Ipv4Addr::new(127, 0, 2, 100));
+    assert_eq!(offer.giaddr, RELAY_IP);
+    assert!(offer.options.contains(&DhcpOption::Router(vec![Ipv4Addr::new(127, 0, 2, 1)])));
+}
+
+#[test]
+fn relay_agent_information_from_a_direct_client_is_not_echoed() {
+    let relay = bind(RELAY_IP);
+    let server = start_with_relay(&relay);
+    let client = bind(Ipv4Addr::LOCALHOST);
+
+    let info = RelayAgentInfo::new(vec![(relay_agent::CIRCUIT_ID, b"Gi0/12".to_vec())]);
+    let mut discover = request(MessageType::Discover, 0x2203, 9);
+    discover.set_option(DhcpOption::RelayAgentInformation(info));
+    let (offer, _) = exchange(&client, server.addr, &discover).expect("OFFER direct");
+
+    assert_eq!(offer.message_type(), Some(MessageType::Offer));
+    assert_eq!(offer.option(options::RELAY_AGENT_INFORMATION), None);
+}
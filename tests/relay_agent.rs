@@ -0,0 +1,69 @@
+// Binaire `relay` entre un client du sous-réseau 127.0.1.0/24 et le serveur
+// sur 127.0.0.1 : retransmission avec giaddr, comptage des sauts, option 82.
+mod common;
+
+use std::net::Ipv4Addr;
+
+use common::{bind, exchange, free_port, recv_reply, request, TestRelay, TestServer};
+use dhcp_demo::message::MessageType;
+use dhcp_demo::options::{self, DhcpOption};
+
+const RELAY_IP: Ipv4Addr = Ipv4Addr::new(127, 0, 1, 1);
+const CLIENT_IP: Ipv4Addr = Ipv4Addr::new(127, 0, 1, 50);
+
+// Serveur desservant 127.0.1.0/24 et relais sur 127.0.1.1 pointant vers lui
+fn start(relay_env: &[(&str, String)]) -> (TestServer, TestRelay) {
+    let relay_addr = free_port(RELAY_IP);
+    let server = TestServer::start(&[
+        ("DHCP_RELAY_SCOPES", "127.0.1.0/24@127.0.1.1=127.0.1.100-127.0.1.110".to_string()),
+        ("DHCP_RELAY_PORT", relay_addr.port().to_string()),
+    ]);
+    let relay = TestRelay::start(relay_addr, &[server.addr], relay_env);
+    (server, relay)
+}
+
+#[test]
+fn client_obtains_a_lease_through_the_relay() {
+    let (_server, relay) = start(&[]);
+    let client = bind(CLIENT_IP);
+
+    let discover = request(MessageType::Discover, 0x2301, 1);
+    let (offer, from) = exchange(&client, relay.addr, &discover).expect("OFFER via le relais");
+    assert_eq!(from, relay.addr);
+    assert_eq!(offer.message_type(), Some(MessageType::Offer));
+    assert_eq!(offer.yiaddr, Ipv4Addr::new(127, 0, 1, 100));
+    assert_eq!(offer.giaddr, RELAY_IP);
+    assert!(offer.options.contains(&DhcpOption::Router(vec![RELAY_IP])));
+
+    let mut req = request(MessageType::Request, 0x2301, 1);
+    req.set_option(DhcpOption::RequestedIpAddress(offer.yiaddr));
+    req.set_option(DhcpOption::ServerIdentifier(Ipv4Addr::LOCALHOST));
+    let (ack, _) = exchange(&client, relay.addr, &req).expect("ACK via le relais");
+    assert_eq!(ack.message_type(), Some(MessageType::Ack));
+    assert_eq!(ack.yiaddr, offer.yiaddr);
+}
+
+#[test]
+fn relay_agent_information_is_removed_before_delivery() {
+    let (_server, relay) = start(&[("DHCP_RELAY_CIRCUIT_ID", "Gi0/1".to_string())]);
+    let client = bind(CLIENT_IP);
+
+    let discover = request(MessageType::Discover, 0x2302, 2);
+    let (offer, _) = exchange(&client, relay.addr, &discover).expect("OFFER via le relais");
+    assert_eq!(offer.message_type(), Some(MessageType::Offer));
+    assert_eq!(offer.option(options::RELAY_AGENT_INFORMATION), None);
+}
+
+#[test]
+fn request_over_the_hop_limit_is_dropped() {
+    let (_server, relay) = start(&[]);
+    let client = bind(CLIENT_IP);
+    // Relais prêt : une requête ordinaire obtient une réponse
+    let discover = request(MessageType::Discover, 0x2303, 3);
+    exchange(&client, relay.addr, &discover).expect("OFFER via le relais");
+
+    let mut looping = request(MessageType::Discover, 0x2304, 4);
+    looping.hops = 16;
+    client.send_to(&looping.to_bytes(), relay.addr).unwrap();
+    assert!(recv_reply(&client, looping.xid).is_none());
+}
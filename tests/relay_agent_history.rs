@@ -0,0 +1,66 @@
+// Historique des baux : circuit et relais (option 82) par lesquels le client est arrivé.
+mod common;
+
+use std::net::Ipv4Addr;
+
+use common::{bind, handle, memory_config, memory_server, obtain_lease, relayed_server, relayed_with_agent_info};
+use dhcp_demo::db::SqliteStore;
+use dhcp_demo::message::MessageType;
+use dhcp_demo::options::DhcpOption;
+use dhcp_demo::relay_agent::{CIRCUIT_ID, REMOTE_ID};
+use dhcp_demo::server::DhcpServer;
+
+// Statut, circuit et relais enregistrés pour l'adresse `ip`
+fn logged(server: &DhcpServer<SqliteStore>, ip: Ipv4Addr) -> (String, Option<String>, Option<String>) {
+    server.store.flush();
+    let conn = server.store.conn.lock().unwrap();
+    conn.query_row(
+        "SELECT status, circuit_id, remote_id FROM leases WHERE ip = ?1 ORDER BY id DESC LIMIT 1",
+        [ip.to_string()],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )
+    .unwrap()
+}
+
+#[test]
+fn relayed_lease_records_circuit_and_remote_ids() {
+    let client = bind(Ipv4Addr::LOCALHOST);
+    let mut server = relayed_server(&client);
+    let suboptions: &[(u8, &[u8])] = &[(CIRCUIT_ID, b"Gi0/7"), (REMOTE_ID, b"sw1")];
+
+    let offer = handle(&mut server, &client, &relayed_with_agent_info(MessageType::Discover, 0x2201, 1, suboptions)).expect("OFFER");
+    assert_eq!(
+        logged(&server, offer.yiaddr),
+        ("OFFERED".to_string(), Some("Gi0/7".to_string()), Some("sw1".to_string()))
+    );
+
+    let mut req = relayed_with_agent_info(MessageType::Request, 0x2201, 1, suboptions);
+    req.set_option(DhcpOption::RequestedIpAddress(offer.yiaddr));
+    req.set_option(DhcpOption::ServerIdentifier(Ipv4Addr::LOCALHOST));
+    let ack = handle(&mut server, &client, &req).expect("ACK");
+    assert_eq!(ack.message_type(), Some(MessageType::Ack));
+    assert_eq!(
+        logged(&server, ack.yiaddr),
+        ("ACKNOWLEDGED".to_string(), Some("Gi0/7".to_string()), Some("sw1".to_string()))
+    );
+}
+
+#[test]
+fn binary_identifiers_are_logged_in_hex() {
+    let client = bind(Ipv4Addr::LOCALHOST);
+    let mut server = relayed_server(&client);
+    let suboptions: &[(u8, &[u8])] = &[(CIRCUIT_ID, &[0x00, 0x07]), (REMOTE_ID, &[0x02, 0x00, 0x5e, 0x10, 0x00, 0x01])];
+
+    let offer = handle(&mut server, &client, &relayed_with_agent_info(MessageType::Discover, 0x2211, 1, suboptions)).expect("OFFER");
+    let (_, circuit_id, remote_id) = logged(&server, offer.yiaddr);
+    assert_eq!(circuit_id.as_deref(), Some("0007"));
+    assert_eq!(remote_id.as_deref(), Some("02005E100001"));
+}
+
+#[test]
+fn direct_lease_has_no_relay_ids() {
+    let mut server = memory_server(memory_config());
+    let client = bind(Ipv4Addr::LOCALHOST);
+    let ip = obtain_lease(&mut server, &client, 0x2221, 1);
+    assert_eq!(logged(&server, ip), ("ACKNOWLEDGED".to_string(), None, None));
+}
@@ -0,0 +1,92 @@
+// DHCPRELEASE : seul le détenteur du bail, auprès du serveur qui l'a accordé,
+// peut rendre son adresse (RFC 2131, 4.4.6).
+mod common;
+
+use std::net::{Ipv4Addr, UdpSocket};
+
+use common::{bind, handle, memory_config, memory_server, obtain_lease_for, request};
+use dhcp_demo::db::SqliteStore;
+use dhcp_demo::lease::LeaseState;
+use dhcp_demo::message::{DhcpMessage, MessageType};
+use dhcp_demo::options::DhcpOption;
+use dhcp_demo::server::DhcpServer;
+
+const CLIENT_ID: [u8; 4] = [0, b'c', b'2', b'0'];
+
+fn with_client_id(mut msg: DhcpMessage) -> DhcpMessage {
+    msg.set_option(DhcpOption::ClientIdentifier(CLIENT_ID.to_vec()));
+    msg
+}
+
+fn release(xid: u32, host: u8, ciaddr: Ipv4Addr, server_id: Ipv4Addr) -> DhcpMessage {
+    let mut msg = with_client_id(request(MessageType::Release, xid, host));
+    msg.ciaddr = ciaddr;
+    msg.set_option(DhcpOption::ServerIdentifier(server_id));
+    msg
+}
+
+// Dernier statut enregistré pour l'adresse `ip`
+fn last_status(server: &DhcpServer<SqliteStore>, ip: Ipv4Addr) -> String {
+    server.store.flush();
+    let conn = server.store.conn.lock().unwrap();
+    conn.query_row(
+        "SELECT status FROM leases WHERE ip = ?1 ORDER BY id DESC LIMIT 1",
+        [ip.to_string()],
+        |row| row.get(0),
+    )
+    .unwrap()
+}
+
+// Le bail du client est toujours confirmé et son adresse n'est proposée à personne d'autre
+fn assert_still_bound(server: &mut DhcpServer<SqliteStore>, client: &UdpSocket, ip: Ipv4Addr, xid: u32) {
+    let lease = server.leases.values().find(|lease| lease.ip == ip).expect("bail toujours connu");
+    assert_eq!(lease.state, LeaseState::Bound);
+    assert_eq!(last_status(server, ip), "ACKNOWLEDGED");
+    // Allocation séquentielle : l'adresse, la plus basse, serait reproposée si elle était libre
+    let offer = handle(server, client, &request(MessageType::Discover, xid, 2)).expect("OFFER");
+    assert_ne!(offer.yiaddr, ip);
+}
+
+#[test]
+fn release_from_another_chaddr_is_ignored() {
+    let mut server = memory_server(memory_config());
+    let client = bind(Ipv4Addr::LOCALHOST);
+    let ip = obtain_lease_for(&mut server, &client, &with_client_id(request(MessageType::Discover, 0x2001, 1)));
+
+    // Même identifiant client, autre adresse matérielle
+    assert!(handle(&mut server, &client, &release(0x2002, 9, ip, Ipv4Addr::LOCALHOST)).is_none());
+    assert_still_bound(&mut server, &client, ip, 0x2003);
+}
+
+#[test]
+fn release_of_another_address_is_ignored() {
+    let mut server = memory_server(memory_config());
+    let client = bind(Ipv4Addr::LOCALHOST);
+    let ip = obtain_lease_for(&mut server, &client, &with_client_id(request(MessageType::Discover, 0x2011, 1)));
+
+    assert!(handle(&mut server, &client, &release(0x2012, 1, Ipv4Addr::new(127, 0, 0, 105), Ipv4Addr::LOCALHOST)).is_none());
+    assert_still_bound(&mut server, &client, ip, 0x2013);
+}
+
+#[test]
+fn release_for_another_server_is_ignored() {
+    let mut server = memory_server(memory_config());
+    let client = bind(Ipv4Addr::LOCALHOST);
+    let ip = obtain_lease_for(&mut server, &client, &with_client_id(request(MessageType::Discover, 0x2021, 1)));
+
+    assert!(handle(&mut server, &client, &release(0x2022, 1, ip, Ipv4Addr::new(127, 0, 0, 254))).is_none());
+    assert_still_bound(&mut server, &client, ip, 0x2023);
+}
+
+#[test]
+fn release_from_the_holder_frees_the_address() {
+    let mut server = memory_server(memory_config());
+    let client = bind(Ipv4Addr::LOCALHOST);
+    let ip = obtain_lease_for(&mut server, &client, &with_client_id(request(MessageType::Discover, 0x2031, 1)));
+
+    assert!(handle(&mut server, &client, &release(0x2032, 1, ip, Ipv4Addr::LOCALHOST)).is_none());
+    assert!(server.leases.is_empty());
+    assert_eq!(last_status(&server, ip), "RELEASED");
+    let offer = handle(&mut server, &client, &request(MessageType::Discover, 0x2033, 2)).expect("OFFER");
+    assert_eq!(offer.yiaddr, ip);
+}
@@ -0,0 +1,91 @@
+// Table de destination des réponses serveur (RFC 2131, 4.1).
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+
+use dhcp_demo::message::{DhcpMessage, MessageType, ReplyDestination, BOOTREQUEST, CLIENT_PORT, FLAG_BROADCAST, SERVER_PORT};
+
+const MAC: [u8; 6] = [0x02, 0, 0, 0, 0, 0x25];
+const RELAY_PORT: u16 = SERVER_PORT;
+
+fn addr(ip: Ipv4Addr, port: u16) -> SocketAddr {
+    SocketAddr::new(ip.into(), port)
+}
+
+fn datagram(ip: Ipv4Addr, port: u16) -> ReplyDestination {
+    ReplyDestination::Ip(addr(ip, port))
+}
+
+fn broadcast() -> ReplyDestination {
+    datagram(Ipv4Addr::BROADCAST, CLIENT_PORT)
+}
+
+// Client en INIT : source 0.0.0.0:68
+fn init_client() -> SocketAddr {
+    addr(Ipv4Addr::UNSPECIFIED, CLIENT_PORT)
+}
+
+fn reply_to(request: &DhcpMessage, msg_type: MessageType) -> DhcpMessage {
+    let mut reply = request.reply(msg_type);
+    reply.ciaddr = request.ciaddr;
+    reply
+}
+
+#[test]
+fn relayed_reply_goes_to_the_relay() {
+    let mut discover = DhcpMessage::new(BOOTREQUEST, MessageType::Discover, 1, MAC);
+    discover.giaddr = Ipv4Addr::new(10, 0, 1, 1);
+    discover.flags = FLAG_BROADCAST;
+    let offer = reply_to(&discover, MessageType::Offer);
+    assert_eq!(offer.reply_destination(init_client(), RELAY_PORT), datagram(Ipv4Addr::new(10, 0, 1, 1), RELAY_PORT));
+
+    let nak = reply_to(&discover, MessageType::Nak);
+    assert_eq!(nak.reply_destination(init_client(), 6868), datagram(Ipv4Addr::new(10, 0, 1, 1), 6868));
+}
+
+#[test]
+fn init_client_with_broadcast_flag_gets_a_broadcast() {
+    let mut discover = DhcpMessage::new(BOOTREQUEST, MessageType::Discover, 2, MAC);
+    discover.flags = FLAG_BROADCAST;
+    let mut offer = reply_to(&discover, MessageType::Offer);
+    offer.yiaddr = Ipv4Addr::new(192, 168, 1, 30);
+    assert_eq!(offer.reply_destination(init_client(), RELAY_PORT), broadcast());
+}
+
+#[test]
+fn init_client_without_broadcast_flag_gets_a_link_unicast() {
+    let discover = DhcpMessage::new(BOOTREQUEST, MessageType::Discover, 3, MAC);
+    let mut offer = reply_to(&discover, MessageType::Offer);
+    offer.yiaddr = Ipv4Addr::new(192, 168, 1, 30);
+    let link = ReplyDestination::Link { chaddr: MAC, addr: SocketAddrV4::new(offer.yiaddr, CLIENT_PORT) };
+    assert_eq!(offer.reply_destination(init_client(), RELAY_PORT), link);
+
+    // Sans adresse Ethernet exploitable, seule la diffusion atteint le client
+    offer.hlen = 16;
+    assert_eq!(offer.reply_destination(init_client(), RELAY_PORT), broadcast());
+}
+
+#[test]
+fn renewing_client_is_answered_at_ciaddr() {
+    let mut request = DhcpMessage::new(BOOTREQUEST, MessageType::Request, 4, MAC);
+    request.ciaddr = Ipv4Addr::new(192, 168, 1, 20);
+    let ack = reply_to(&request, MessageType::Ack);
+    let src = addr(Ipv4Addr::new(192, 168, 1, 20), CLIENT_PORT);
+    assert_eq!(ack.reply_destination(src, RELAY_PORT), datagram(Ipv4Addr::new(192, 168, 1, 20), CLIENT_PORT));
+}
+
+#[test]
+fn nak_is_always_broadcast_to_local_clients() {
+    let mut request = DhcpMessage::new(BOOTREQUEST, MessageType::Request, 5, MAC);
+    request.ciaddr = Ipv4Addr::new(192, 168, 1, 20);
+    let nak = request.reply(MessageType::Nak);
+    let src = addr(Ipv4Addr::new(192, 168, 1, 20), CLIENT_PORT);
+    assert_eq!(nak.reply_destination(src, RELAY_PORT), broadcast());
+}
+
+#[test]
+fn client_on_another_port_is_answered_at_its_source() {
+    let mut discover = DhcpMessage::new(BOOTREQUEST, MessageType::Discover, 6, MAC);
+    discover.flags = FLAG_BROADCAST;
+    let offer = reply_to(&discover, MessageType::Offer);
+    let src = addr(Ipv4Addr::LOCALHOST, 45000);
+    assert_eq!(offer.reply_destination(src, RELAY_PORT), ReplyDestination::Ip(src));
+}
@@ -0,0 +1,109 @@
+// Réservations statiques : par adresse MAC ou par port de relais (option 82),
+// hors du pool dynamique et conservées dans la base.
+mod common;
+
+use std::net::Ipv4Addr;
+
+use common::{bind, handle, memory_config, memory_server, obtain_lease, relayed_server, relayed_with_agent_info, request};
+use dhcp_demo::db;
+use dhcp_demo::message::MessageType;
+use dhcp_demo::options::{self, DhcpOption};
+use dhcp_demo::relay_agent::{CIRCUIT_ID, REMOTE_ID};
+
+const RESERVED_IP: Ipv4Addr = Ipv4Addr::new(127, 0, 0, 150);
+
+#[test]
+fn reserved_mac_gets_its_address() {
+    let mut server = memory_server(memory_config());
+    let client = bind(Ipv4Addr::LOCALHOST);
+    server.ajouter_reservation("02:00:00:00:00:01", "127.0.0.150", "imprimante", "");
+
+    let offer = handle(&mut server, &client, &request(MessageType::Discover, 0x1101, 1)).expect("OFFER");
+    assert_eq!(offer.yiaddr, RESERVED_IP);
+    assert!(offer.options.contains(&DhcpOption::HostName("imprimante".to_string())));
+    assert_eq!(obtain_lease(&mut server, &client, 0x1102, 1), RESERVED_IP);
+}
+
+#[test]
+fn reserved_address_is_refused_to_other_clients() {
+    let mut server = memory_server(memory_config());
+    let client = bind(Ipv4Addr::LOCALHOST);
+    // Réservation prise dans la plage dynamique : elle n'est plus distribuée
+    server.ajouter_reservation("02:00:00:00:00:01", "127.0.0.100", "", "");
+
+    let offer = handle(&mut server, &client, &request(MessageType::Discover, 0x1111, 2)).expect("OFFER");
+    assert_ne!(offer.yiaddr, Ipv4Addr::new(127, 0, 0, 100));
+
+    let mut req = request(MessageType::Request, 0x1112, 2);
+    req.set_option(DhcpOption::RequestedIpAddress(Ipv4Addr::new(127, 0, 0, 100)));
+    req.set_option(DhcpOption::ServerIdentifier(Ipv4Addr::LOCALHOST));
+    let nak = handle(&mut server, &client, &req).expect("NAK");
+    assert_eq!(nak.message_type(), Some(MessageType::Nak));
+}
+
+#[test]
+fn reservation_by_circuit_follows_the_switch_port() {
+    let client = bind(Ipv4Addr::LOCALHOST);
+    let mut server = relayed_server(&client);
+    server.ajouter_reservation("circuit:Gi0/7@sw1", "127.0.0.150", "", "");
+
+    // N'importe quel équipement branché sur ce port reçoit l'adresse réservée
+    let offer = handle(&mut server, &client, &relayed_with_agent_info(MessageType::Discover, 0x1121, 1, &[(CIRCUIT_ID, b"Gi0/7"), (REMOTE_ID, b"sw1")])).expect("OFFER");
+    assert_eq!(offer.yiaddr, RESERVED_IP);
+
+    // Même port sur un autre commutateur : pool dynamique
+    let offer = handle(&mut server, &client, &relayed_with_agent_info(MessageType::Discover, 0x1122, 2, &[(CIRCUIT_ID, b"Gi0/7"), (REMOTE_ID, b"sw2")])).expect("OFFER");
+    assert_ne!(offer.yiaddr, RESERVED_IP);
+}
+
+#[test]
+fn reservations_are_persisted() {
+    let mut first = memory_server(memory_config());
+    first.ajouter_reservation("02:00:00:00:00:01", "127.0.0.150", "imprimante", "15=atelier.lan");
+    first.ajouter_reservation("circuit:Gi0/7", "127.0.0.151", "", "");
+    first.supprimer_reservation("127.0.0.151");
+    first.store.flush();
+
+    let reservations = db::load_reservations(&first.store.conn.lock().unwrap()).unwrap();
+    assert_eq!(reservations.len(), 1);
+    assert_eq!(reservations[0].mac.as_deref(), Some("020000000001"));
+    assert_eq!(reservations[0].ip, RESERVED_IP);
+    assert_eq!(reservations[0].hostname.as_deref(), Some("imprimante"));
+    assert_eq!(reservations[0].options, vec![DhcpOption::DomainName("atelier.lan".to_string())]);
+
+    let mut second = memory_server(memory_config());
+    second.restore_reservations(reservations);
+    let client = bind(Ipv4Addr::LOCALHOST);
+    let offer = handle(&mut second, &client, &request(MessageType::Discover, 0x1131, 1)).expect("OFFER");
+    assert_eq!(offer.yiaddr, RESERVED_IP);
+}
+
+#[test]
+fn reservation_options_are_sent_to_the_host() {
+    let mut config = memory_config();
+    config.router = Some(Ipv4Addr::new(127, 0, 0, 254));
+    let mut server = memory_server(config);
+    let client = bind(Ipv4Addr::LOCALHOST);
+    server.ajouter_reservation("02:00:00:00:00:01", "127.0.0.150", "", "3=127.0.0.1; 6=1.1.1.1,9.9.9.9; 43=0x0104c0a80001");
+
+    let offer = handle(&mut server, &client, &request(MessageType::Discover, 0x1141, 1)).expect("OFFER");
+    assert_eq!(offer.yiaddr, RESERVED_IP);
+    // La passerelle de l'hôte remplace celle du serveur
+    assert_eq!(offer.option(options::ROUTER), Some(&DhcpOption::Router(vec![Ipv4Addr::LOCALHOST])));
+    assert!(offer.options.contains(&DhcpOption::DomainNameServer(vec![Ipv4Addr::new(1, 1, 1, 1), Ipv4Addr::new(9, 9, 9, 9)])));
+    assert!(offer.options.contains(&DhcpOption::Unknown(43, vec![0x01, 0x04, 0xc0, 0xa8, 0x00, 0x01])));
+
+    // Les autres clients gardent la configuration du serveur
+    let other = handle(&mut server, &client, &request(MessageType::Discover, 0x1142, 2)).expect("OFFER");
+    assert_eq!(other.option(options::ROUTER), Some(&DhcpOption::Router(vec![Ipv4Addr::new(127, 0, 0, 254)])));
+    assert!(other.option(43).is_none());
+}
+
+#[test]
+fn reservation_with_invalid_options_is_refused() {
+    let mut server = memory_server(memory_config());
+    for options in ["3", "x=1", "43=0x0g", "43=0x123", "51=3600", "15="] {
+        server.ajouter_reservation("02:00:00:00:00:01", "127.0.0.150", "", options);
+        assert!(server.reservations.is_empty(), "{}", options);
+    }
+}
@@ -0,0 +1,49 @@
+// Sélection entre plusieurs serveurs : un REQUEST désignant un autre serveur
+// (option 54) fait retirer l'offre en attente.
+mod common;
+
+use std::net::Ipv4Addr;
+
+use common::{bind, exchange, recv_reply, request, TestServer};
+use dhcp_demo::message::MessageType;
+use dhcp_demo::options::DhcpOption;
+
+const OTHER_SERVER: Ipv4Addr = Ipv4Addr::new(127, 0, 0, 254);
+
+#[test]
+fn offer_carries_the_server_identifier() {
+    let server = TestServer::start(&[]);
+    let client = bind(Ipv4Addr::LOCALHOST);
+
+    let discover = request(MessageType::Discover, 0x2401, 1);
+    let (offer, _) = exchange(&client, server.addr, &discover).expect("OFFER");
+    assert_eq!(offer.server_identifier(), Some(Ipv4Addr::LOCALHOST));
+}
+
+#[test]
+fn offer_is_withdrawn_when_another_server_is_selected() {
+    let server = TestServer::start(&[]);
+    let client = bind(Ipv4Addr::LOCALHOST);
+
+    let discover = request(MessageType::Discover, 0x2402, 2);
+    let (offer, _) = exchange(&client, server.addr, &discover).expect("OFFER");
+
+    // Le client retient l'offre d'un autre serveur : pas de réponse
+    let mut req = request(MessageType::Request, 0x2402, 2);
+    req.set_option(DhcpOption::RequestedIpAddress(Ipv4Addr::new(127, 0, 0, 200)));
+    req.set_option(DhcpOption::ServerIdentifier(OTHER_SERVER));
+    client.send_to(&req.to_bytes(), server.addr).unwrap();
+    assert!(recv_reply(&client, req.xid).is_none());
+
+    // L'adresse offerte est de nouveau disponible pour un autre client
+    let other = request(MessageType::Discover, 0x2403, 3);
+    let (second, _) = exchange(&client, server.addr, &other).expect("OFFER");
+    assert_eq!(second.yiaddr, offer.yiaddr);
+
+    // Et l'offre retirée ne peut plus être confirmée auprès de ce serveur
+    let mut late = request(MessageType::Request, 0x2404, 2);
+    late.set_option(DhcpOption::RequestedIpAddress(offer.yiaddr));
+    late.set_option(DhcpOption::ServerIdentifier(Ipv4Addr::LOCALHOST));
+    let (nak, _) = exchange(&client, server.addr, &late).expect("NAK");
+    assert_eq!(nak.message_type(), Some(MessageType::Nak));
+}
@@ -0,0 +1,58 @@
+// Allocation « collante » : un client qui revient retrouve la dernière adresse
+// qu'il détenait, tant qu'elle est libre, y compris après un redémarrage.
+mod common;
+
+use std::net::Ipv4Addr;
+
+use common::{bind, handle, memory_config, memory_server, obtain_lease, release, request};
+use dhcp_demo::db;
+use dhcp_demo::message::MessageType;
+
+#[test]
+fn returning_client_gets_its_previous_address() {
+    let mut server = memory_server(memory_config());
+    let client = bind(Ipv4Addr::LOCALHOST);
+    let first = obtain_lease(&mut server, &client, 0x1201, 1);
+    let second = obtain_lease(&mut server, &client, 0x1202, 2);
+    release(&mut server, &client, 0x1203, 1, first);
+    release(&mut server, &client, 0x1204, 2, second);
+
+    // L'allocation séquentielle proposerait la plus basse adresse libre
+    let offer = handle(&mut server, &client, &request(MessageType::Discover, 0x1205, 2)).expect("OFFER");
+    assert_eq!(offer.yiaddr, second);
+    assert_ne!(offer.yiaddr, first);
+}
+
+#[test]
+fn previous_addresses_survive_a_restart() {
+    let mut first_run = memory_server(memory_config());
+    let client = bind(Ipv4Addr::LOCALHOST);
+    let first = obtain_lease(&mut first_run, &client, 0x1211, 1);
+    let second = obtain_lease(&mut first_run, &client, 0x1212, 2);
+    release(&mut first_run, &client, 0x1213, 1, first);
+    release(&mut first_run, &client, 0x1214, 2, second);
+    first_run.store.flush();
+
+    let previous_ips = db::load_previous_ips(&first_run.store.conn.lock().unwrap()).unwrap();
+    assert_eq!(previous_ips.get("020000000002"), Some(&second));
+
+    let mut second_run = memory_server(memory_config());
+    second_run.previous_ips = previous_ips;
+    let offer = handle(&mut second_run, &client, &request(MessageType::Discover, 0x1215, 2)).expect("OFFER");
+    assert_eq!(offer.yiaddr, second);
+}
+
+#[test]
+fn taken_previous_address_falls_back_to_the_pool() {
+    let mut server = memory_server(memory_config());
+    let client = bind(Ipv4Addr::LOCALHOST);
+    let previous = obtain_lease(&mut server, &client, 0x1221, 1);
+    release(&mut server, &client, 0x1222, 1, previous);
+
+    // Un autre client occupe entre-temps l'adresse précédente
+    assert_eq!(obtain_lease(&mut server, &client, 0x1223, 2), previous);
+
+    let offer = handle(&mut server, &client, &request(MessageType::Discover, 0x1224, 1)).expect("OFFER");
+    assert_ne!(offer.yiaddr, previous);
+    assert_eq!(offer.yiaddr, Ipv4Addr::new(127, 0, 0, 101));
+}